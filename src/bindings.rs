@@ -0,0 +1,213 @@
+use console_engine::KeyCode;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Action {
+    Open,
+    Flag,
+    AutoFlag,
+    Chord,
+    Hint,
+    Sweep,
+    Undo,
+    GiveUp,
+    Spacing,
+    Border,
+    CursorStyle,
+    Probability,
+    MineHint,
+    Peek,
+    Stats,
+    Reveal,
+    Share,
+    Mode,
+    Restart,
+    Menu,
+    Help,
+}
+
+impl Action {
+    pub const ALL: [Action; 21] = [
+        Action::Open,
+        Action::Flag,
+        Action::AutoFlag,
+        Action::Chord,
+        Action::Hint,
+        Action::Sweep,
+        Action::Undo,
+        Action::GiveUp,
+        Action::Spacing,
+        Action::Border,
+        Action::CursorStyle,
+        Action::Probability,
+        Action::MineHint,
+        Action::Peek,
+        Action::Stats,
+        Action::Reveal,
+        Action::Share,
+        Action::Mode,
+        Action::Restart,
+        Action::Menu,
+        Action::Help,
+    ];
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Action::Open => "Open cell",
+            Action::Flag => "Toggle flag",
+            Action::AutoFlag => "Flag around number",
+            Action::Chord => "Open around number",
+            Action::Hint => "Solve one step",
+            Action::Sweep => "Solve until stuck",
+            Action::Undo => "Undo",
+            Action::GiveUp => "Give up",
+            Action::Spacing => "Toggle spacing",
+            Action::Border => "Toggle border",
+            Action::CursorStyle => "Toggle cursor style",
+            Action::Probability => "Toggle mine odds",
+            Action::MineHint => "Show mines (practice)",
+            Action::Peek => "Show numbers (practice)",
+            Action::Stats => "Show stats",
+            Action::Reveal => "Cycle loss reveal",
+            Action::Share => "Share board code",
+            Action::Mode => "Toggle open/flag mode",
+            Action::Restart => "Restart",
+            Action::Menu => "Main menu",
+            Action::Help => "Toggle help",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyBindings {
+    pub open: KeyCode,
+    pub flag: KeyCode,
+    pub auto_flag: KeyCode,
+    pub chord: KeyCode,
+    pub hint: KeyCode,
+    pub sweep: KeyCode,
+    pub undo: KeyCode,
+    pub give_up: KeyCode,
+    pub spacing: KeyCode,
+    pub border: KeyCode,
+    pub cursor_style: KeyCode,
+    pub probability: KeyCode,
+    pub mine_hint: KeyCode,
+    pub peek: KeyCode,
+    pub stats: KeyCode,
+    pub reveal: KeyCode,
+    pub share: KeyCode,
+    pub mode: KeyCode,
+    pub restart: KeyCode,
+    pub menu: KeyCode,
+    pub help: KeyCode,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            open: KeyCode::Char(' '),
+            flag: KeyCode::Char('f'),
+            auto_flag: KeyCode::Char('a'),
+            chord: KeyCode::Char('d'),
+            hint: KeyCode::Char('s'),
+            sweep: KeyCode::Char('e'),
+            undo: KeyCode::Char('u'),
+            give_up: KeyCode::Char('g'),
+            spacing: KeyCode::Char('c'),
+            border: KeyCode::Char('b'),
+            cursor_style: KeyCode::Char('v'),
+            probability: KeyCode::Char('p'),
+            mine_hint: KeyCode::Char('i'),
+            peek: KeyCode::Char('k'),
+            stats: KeyCode::Enter,
+            reveal: KeyCode::Char('o'),
+            share: KeyCode::Char('w'),
+            mode: KeyCode::Char('t'),
+            restart: KeyCode::Char('r'),
+            menu: KeyCode::Char('m'),
+            help: KeyCode::Char('h'),
+        }
+    }
+}
+
+impl KeyBindings {
+    pub fn key(&self, action: Action) -> KeyCode {
+        match action {
+            Action::Open => self.open,
+            Action::Flag => self.flag,
+            Action::AutoFlag => self.auto_flag,
+            Action::Chord => self.chord,
+            Action::Hint => self.hint,
+            Action::Sweep => self.sweep,
+            Action::Undo => self.undo,
+            Action::GiveUp => self.give_up,
+            Action::Spacing => self.spacing,
+            Action::Border => self.border,
+            Action::CursorStyle => self.cursor_style,
+            Action::Probability => self.probability,
+            Action::MineHint => self.mine_hint,
+            Action::Peek => self.peek,
+            Action::Stats => self.stats,
+            Action::Reveal => self.reveal,
+            Action::Share => self.share,
+            Action::Mode => self.mode,
+            Action::Restart => self.restart,
+            Action::Menu => self.menu,
+            Action::Help => self.help,
+        }
+    }
+
+    /// Returns all actions whose bound key is reported as pressed.
+    pub fn pressed<F>(&self, is_pressed: F) -> Vec<Action> where F: Fn(KeyCode) -> bool {
+        Action::ALL.into_iter()
+            .filter(|action| is_pressed(self.key(*action)))
+            .collect()
+    }
+
+    pub fn help_lines(&self) -> Vec<String> {
+        Action::ALL.iter()
+            .map(|action| format!("{:>5}  {}", key_name(self.key(*action)), action.description()))
+            .collect()
+    }
+}
+
+pub(crate) fn key_name(key: KeyCode) -> String {
+    match key {
+        KeyCode::Char(' ') => String::from("Space"),
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::Enter => String::from("Enter"),
+        KeyCode::Tab => String::from("Tab"),
+        KeyCode::Backspace => String::from("Bksp"),
+        other => format!("{:?}", other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use console_engine::KeyCode;
+    use crate::bindings::{Action, KeyBindings};
+
+    #[test]
+    fn default_bindings() {
+        let bindings = KeyBindings::default();
+        assert_eq!(vec![Action::Open], bindings.pressed(|key| key == KeyCode::Char(' ')));
+        assert_eq!(vec![Action::Flag], bindings.pressed(|key| key == KeyCode::Char('f')));
+    }
+
+    #[test]
+    fn custom_binding_replaces_default() {
+        let bindings = KeyBindings {
+            flag: KeyCode::Char('x'),
+            ..KeyBindings::default()
+        };
+        assert_eq!(vec![Action::Flag], bindings.pressed(|key| key == KeyCode::Char('x')));
+        assert!(bindings.pressed(|key| key == KeyCode::Char('f')).is_empty());
+    }
+
+    #[test]
+    fn help_lists_every_action() {
+        let lines = KeyBindings::default().help_lines();
+        assert_eq!(Action::ALL.len(), lines.len());
+        assert_eq!("Space  Open cell", lines[0]);
+    }
+}
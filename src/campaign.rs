@@ -0,0 +1,231 @@
+use std::time::Duration;
+use console_engine::KeyCode;
+use console_engine::screen::Screen;
+use crate::bindings::key_name;
+use crate::game::{Game, GameSettings, GameStats};
+use crate::geom::Size2D;
+use crate::input::Input;
+use crate::main_menu::MainMenu;
+use crate::state::{GameState, SystemEvent};
+use crate::stats::score;
+
+const KEY_SUMMARY: KeyCode = KeyCode::Enter;
+const KEY_MENU: KeyCode = KeyCode::Char('m');
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Level {
+    pub size: Size2D,
+    pub mine_count: usize,
+}
+
+impl Level {
+    pub fn new(size: Size2D, mine_count: usize) -> Self {
+        Self {
+            size,
+            mine_count,
+        }
+    }
+
+    /// Boards of increasing size and density, ending at the classic expert layout.
+    pub fn default_levels() -> Vec<Level> {
+        vec![
+            Level::new(Size2D(9, 9), 10),
+            Level::new(Size2D(16, 10), 20),
+            Level::new(Size2D(16, 16), 40),
+            Level::new(Size2D(30, 16), 99),
+        ]
+    }
+
+    fn settings(&self) -> GameSettings {
+        GameSettings {
+            size: self.size.clone(),
+            mine_count: self.mine_count,
+            ..GameSettings::default()
+        }
+    }
+}
+
+/// Plays a sequence of levels, moving on to the next one whenever a board is cleared.
+pub struct Campaign {
+    levels: Vec<Level>,
+    level: usize,
+    game: Game,
+    /// Time and score summed over all cleared levels.
+    total_time: Duration,
+    total_score: u64,
+    /// Set once the campaign is lost or completed and only the summary is shown.
+    finished: bool,
+}
+
+impl Campaign {
+    /// Campaign starting at the first level, `None` if there are no levels or the first board cannot be generated.
+    pub fn new(levels: Vec<Level>) -> Option<Self> {
+        Self::at_level(levels, 0, Duration::ZERO, 0)
+    }
+
+    /// Starts a fresh game of `levels[level]`, `None` if there is no such level or its board cannot be generated.
+    fn at_level(levels: Vec<Level>, level: usize, total_time: Duration, total_score: u64) -> Option<Self> {
+        let settings = levels.get(level)?.settings();
+        let mut game = Game::random(settings).ok()?;
+        let bindings = game.bindings();
+        let keys = vec![
+            format!("{}: Summary", key_name(KEY_SUMMARY)),
+            format!("{}: Retry level  {}: Menu  Esc: Quit", key_name(bindings.restart), key_name(bindings.menu)),
+        ];
+        game.set_result_keys(keys);
+        Some(Self {
+            game,
+            levels,
+            level,
+            total_time,
+            total_score,
+            finished: false,
+        })
+    }
+
+    fn add_result(&mut self, stats: &GameStats) {
+        let level = &self.levels[self.level];
+        self.total_time += stats.duration;
+        self.total_score += score(stats, &level.size, level.mine_count);
+    }
+
+    /// Campaign continuing at the following level after finishing the current one with `stats`,
+    /// or `None` if the board was not cleared or it was the last level.
+    fn next_level(&self, stats: &GameStats) -> Option<Campaign> {
+        if !stats.won || self.level + 1 >= self.levels.len() {
+            return None;
+        }
+        let level = &self.levels[self.level];
+        let total_time = self.total_time + stats.duration;
+        let total_score = self.total_score + score(stats, &level.size, level.mine_count);
+        Self::at_level(self.levels.clone(), self.level + 1, total_time, total_score)
+    }
+
+    fn summary_lines(&self) -> Vec<String> {
+        let cleared = if self.game.stats().is_some_and(|stats| stats.won) { self.level + 1 } else { self.level };
+        vec![
+            String::from(if cleared == self.levels.len() { "Campaign complete!" } else { "Campaign over" }),
+            String::new(),
+            format!("Levels cleared: {}/{}", cleared, self.levels.len()),
+            format!("Total time: {}s", self.total_time.as_secs()),
+            format!("Total score: {}", self.total_score),
+            String::new(),
+            String::from("M: Menu  Esc: Quit"),
+        ]
+    }
+}
+
+impl GameState for Campaign {
+    fn update(&mut self, input: &dyn Input, dt: Duration) -> Option<SystemEvent> {
+        if self.finished {
+            if input.is_key_pressed(KeyCode::Esc) {
+                return Some(SystemEvent::Exit);
+            }
+            if input.is_key_pressed(KEY_MENU) {
+                return Some(SystemEvent::ChangeState(Box::<MainMenu>::default()));
+            }
+            return None;
+        }
+        if self.game.restart_pressed(input) {
+            // Restarting the game alone would leave the campaign and drop the totals.
+            return Self::at_level(self.levels.clone(), self.level, self.total_time, self.total_score)
+                .map(|campaign| SystemEvent::ChangeState(Box::new(campaign)));
+        }
+        if let Some(stats) = self.game.stats().cloned() {
+            if let Some(next) = self.next_level(&stats) {
+                return Some(SystemEvent::ChangeState(Box::new(next)));
+            }
+            if stats.won || input.is_key_pressed(KEY_SUMMARY) {
+                self.add_result(&stats);
+                self.finished = true;
+                return None;
+            }
+        }
+        self.game.update(input, dt)
+    }
+
+    fn needs_redraw(&self) -> bool {
+        self.finished || self.game.needs_redraw()
+    }
+
+    fn draw(&self, screen: &mut Screen) {
+        if !self.finished {
+            self.game.draw(screen);
+            let label = format!("Level {}/{}", self.level + 1, self.levels.len());
+            screen.print(screen.get_width() as i32 - label.len() as i32, 0, &label);
+            return;
+        }
+        let lines = self.summary_lines();
+        let offset_y = screen.get_height() as i32 / 2 - lines.len() as i32 / 2;
+        lines.iter().enumerate().for_each(|(idx, line)| {
+            let offset_x = screen.get_width() as i32 / 2 - line.len() as i32 / 2;
+            screen.print(offset_x, offset_y + idx as i32, line);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use console_engine::KeyCode;
+    use console_engine::screen::Screen;
+    use crate::campaign::{Campaign, Level};
+    use crate::game::GameStats;
+    use crate::geom::Size2D;
+    use crate::input::MockInput;
+    use crate::state::{GameState, SystemEvent};
+
+    fn stats(won: bool) -> GameStats {
+        GameStats {
+            duration: Duration::from_secs(30),
+            cells_opened: 0,
+            flags_used: 0,
+            actions: 0,
+            won,
+        }
+    }
+
+    fn levels() -> Vec<Level> {
+        vec![Level::new(Size2D(4, 4), 2), Level::new(Size2D(6, 5), 4)]
+    }
+
+    #[test]
+    fn win_advances_to_next_level() {
+        let campaign = Campaign::new(levels()).unwrap();
+        let next = campaign.next_level(&stats(true)).unwrap();
+        assert_eq!(1, next.level);
+        assert_eq!(Size2D(6, 5), next.game.settings().size);
+        assert_eq!(4, next.game.settings().mine_count);
+        assert_eq!(Duration::from_secs(30), next.total_time);
+    }
+
+    #[test]
+    fn loss_does_not_advance() {
+        let campaign = Campaign::new(levels()).unwrap();
+        assert!(campaign.next_level(&stats(false)).is_none());
+    }
+
+    #[test]
+    fn last_level_does_not_advance() {
+        let campaign = Campaign::new(levels()).unwrap();
+        let last = campaign.next_level(&stats(true)).unwrap();
+        assert!(last.next_level(&stats(true)).is_none());
+    }
+
+    #[test]
+    fn restart_stays_in_campaign() {
+        let mut campaign = Campaign::new(levels()).unwrap().next_level(&stats(true)).unwrap();
+        let Some(SystemEvent::ChangeState(state)) = campaign.update(&MockInput::pressing(KeyCode::Char('r')), Duration::ZERO) else {
+            panic!("restart did not change the state");
+        };
+        let mut screen = Screen::new(40, 20);
+        state.draw(&mut screen);
+        let label = (31..40).map(|x| screen.get_pxl(x, 0).unwrap().chr).collect::<String>();
+        assert_eq!("Level 2/2", label);
+    }
+
+    #[test]
+    fn no_levels_is_no_campaign() {
+        assert!(Campaign::new(Vec::new()).is_none());
+    }
+}
@@ -1,6 +1,8 @@
+use std::ops::{Index, IndexMut};
 use crate::geom::{Point2D, Size2D};
 
 
+#[derive(Clone)]
 pub struct Vec2D<T> {
     pub size: Size2D,
     data: Vec<Vec<T>>,
@@ -14,6 +16,66 @@ impl<T> Vec2D<T> {
         }
     }
 
+    pub fn from_fn<F>(size: &Size2D, mut f: F) -> Self where F: FnMut(&Point2D) -> T {
+        Self {
+            size: size.clone(),
+            data: (0..size.0)
+                .map(|x| (0..size.1)
+                    .map(|y| f(&Point2D(x, y)))
+                    .collect())
+                .collect(),
+        }
+    }
+
+    /// Returns a new grid with the x and y axes swapped.
+    pub fn transpose(&self) -> Self where T: Clone {
+        Self::from_fn(&self.size.transpose(), |point| {
+            self[&point.transpose()].clone()
+        })
+    }
+
+    /// Returns a new grid turned a quarter clockwise, so the left column becomes the top row.
+    pub fn rotate_cw(&self) -> Self where T: Clone {
+        let height = self.size.1;
+        Self::from_fn(&self.size.transpose(), |point| {
+            self[&Point2D(point.1, height - 1 - point.0)].clone()
+        })
+    }
+
+    /// Returns a new grid turned a quarter counterclockwise, so the top row becomes the left column.
+    pub fn rotate_ccw(&self) -> Self where T: Clone {
+        let width = self.size.0;
+        Self::from_fn(&self.size.transpose(), |point| {
+            self[&Point2D(width - 1 - point.1, point.0)].clone()
+        })
+    }
+
+    /// Returns a new grid mirrored left to right.
+    pub fn flip_horizontal(&self) -> Self where T: Clone {
+        let width = self.size.0;
+        Self::from_fn(&self.size, |point| {
+            self[&Point2D(width - 1 - point.0, point.1)].clone()
+        })
+    }
+
+    /// Returns a new grid mirrored top to bottom.
+    pub fn flip_vertical(&self) -> Self where T: Clone {
+        let height = self.size.1;
+        Self::from_fn(&self.size, |point| {
+            self[&Point2D(point.0, height - 1 - point.1)].clone()
+        })
+    }
+
+    /// Changes the size in place. Values inside both the old and the new size are kept,
+    /// added cells are set to `default` and cells outside the new size are dropped.
+    pub fn resize(&mut self, new_size: &Size2D, default: T) where T: Clone {
+        self.data.truncate(new_size.0);
+        self.data.iter_mut()
+            .for_each(|column| column.resize(new_size.1, default.clone()));
+        self.data.resize(new_size.0, vec![default; new_size.1]);
+        self.size = new_size.clone();
+    }
+
     pub fn get(&self, point2d: &Point2D) -> Option<&T> {
         if !self.size.contains(point2d) {
             None
@@ -30,10 +92,61 @@ impl<T> Vec2D<T> {
         }
     }
 
-    pub fn all_locations(&self) -> Vec<Point2D> {
-        (0..self.size.0)
-            .flat_map(|x| (0..self.size.1)
-                .map(move |y| Point2D(x, y)))
+    /// Exchanges the values at `a` and `b`. Does nothing if either is out of bounds.
+    pub fn swap(&mut self, a: &Point2D, b: &Point2D) {
+        if !self.size.contains(a) || !self.size.contains(b) {
+            return;
+        }
+        if a.0 == b.0 {
+            self.data[a.0].swap(a.1, b.1);
+        } else {
+            let (low, high) = if a.0 < b.0 { (a, b) } else { (b, a) };
+            let (left, right) = self.data.split_at_mut(high.0);
+            std::mem::swap(&mut left[low.0][low.1], &mut right[0][high.1]);
+        }
+    }
+
+    /// Values in row `y` from left to right. The row is spread over all columns of the
+    /// `[x][y]` layout, so this steps through each of them.
+    pub fn row(&self, y: usize) -> Option<impl Iterator<Item = &T>> {
+        (y < self.size.1).then(|| self.data.iter().map(move |column| &column[y]))
+    }
+
+    /// Values in column `x` from top to bottom, read straight from its backing vector.
+    pub fn column(&self, x: usize) -> Option<impl Iterator<Item = &T>> {
+        self.data.get(x).map(|column| column.iter())
+    }
+
+    pub fn fill(&mut self, value: T) where T: Clone {
+        self.data.iter_mut()
+            .for_each(|column| column.fill(value.clone()));
+    }
+
+    /// Returns references to all in-bounds cells surrounding `point`.
+    pub fn neighbours(&self, point: &Point2D) -> Vec<&T> {
+        point.neighbours().iter()
+            .filter_map(|neighbour| self.get(neighbour))
             .collect()
     }
+
+    pub fn all_locations(&self) -> Vec<Point2D> {
+        self.size.iter_points().collect()
+    }
+}
+
+/// Like `get`, but panics if `point` lies outside the grid.
+impl<T> Index<&Point2D> for Vec2D<T> {
+    type Output = T;
+
+    fn index(&self, point: &Point2D) -> &T {
+        self.get(point).unwrap_or_else(|| panic!("{:?} is outside of {:?}", point, self.size))
+    }
+}
+
+/// Like `get_mut`, but panics if `point` lies outside the grid.
+impl<T> IndexMut<&Point2D> for Vec2D<T> {
+    fn index_mut(&mut self, point: &Point2D) -> &mut T {
+        let size = self.size.clone();
+        self.get_mut(point).unwrap_or_else(|| panic!("{:?} is outside of {:?}", point, size))
+    }
 }
@@ -0,0 +1,60 @@
+use std::fs;
+
+const CONFIG_PATH: &str = "bombe.conf";
+const DEFAULT_FRAME_RATE: u32 = 15;
+const MAX_FRAME_RATE: u32 = 120;
+
+/// Startup options read from `bombe.conf`, one `key = value` pair per line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    /// Frames drawn per second, between 1 and 120.
+    pub frame_rate: u32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            frame_rate: DEFAULT_FRAME_RATE,
+        }
+    }
+}
+
+impl Config {
+    /// Reads the config file, falling back to the defaults if it is missing.
+    pub fn load() -> Self {
+        fs::read_to_string(CONFIG_PATH)
+            .map(|text| Self::parse(&text))
+            .unwrap_or_default()
+    }
+
+    /// Parses the config text, skipping comments starting with `#` and ignoring unknown keys
+    /// and values that don't parse.
+    pub fn parse(text: &str) -> Self {
+        let mut config = Self::default();
+        for line in text.lines().map(str::trim).filter(|line| !line.starts_with('#')) {
+            let Some((key, value)) = line.split_once('=') else { continue };
+            if key.trim() == "frame_rate" {
+                if let Ok(frame_rate) = value.trim().parse::<u32>() {
+                    config.frame_rate = frame_rate.clamp(1, MAX_FRAME_RATE);
+                }
+            }
+        }
+        config
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::Config;
+
+    #[test]
+    fn parse_reads_frame_rate() {
+        assert_eq!(30, Config::parse("# faster\nframe_rate = 30\n").frame_rate);
+        assert_eq!(120, Config::parse("frame_rate=1000").frame_rate);
+    }
+
+    #[test]
+    fn parse_keeps_defaults_for_bad_lines() {
+        assert_eq!(Config::default(), Config::parse("frame_rate = fast\nvolume = 3\nnonsense"));
+    }
+}
@@ -0,0 +1,231 @@
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+use console_engine::KeyCode;
+use console_engine::pixel::pxl;
+use console_engine::screen::Screen;
+use crate::game::{field_offset, move_cursor, Game, GameSettings, GenerationError, KeyRepeat, Minefield};
+use crate::geom::{Point2D, Size2D};
+use crate::input::{typed_char, Input};
+use crate::main_menu::MainMenu;
+use crate::state::{GameState, SystemEvent};
+
+const KEY_TOGGLE: KeyCode = KeyCode::Char(' ');
+const KEY_EXPORT: KeyCode = KeyCode::Char('x');
+const KEY_ANALYZE: KeyCode = KeyCode::Char('a');
+const KEY_PLAY: KeyCode = KeyCode::Enter;
+const KEY_MENU: KeyCode = KeyCode::Char('m');
+const KEY_CONFIRM_OVERWRITE: KeyCode = KeyCode::Char('y');
+/// Suggested file name when exporting starts.
+const EXPORT_PATH: &str = "puzzle.txt";
+
+/// File name being typed in for an export.
+struct ExportPrompt {
+    path: String,
+    /// Set once Enter was pressed on an existing file, until the overwrite is answered.
+    confirm_overwrite: bool,
+}
+
+/// Lets the player place mines by hand and play or export the result.
+pub struct Editor {
+    field: Minefield,
+    cursor: Point2D,
+    cursor_repeat: [KeyRepeat; 4],
+    viewport: Point2D,
+    status: Option<String>,
+    export_prompt: Option<ExportPrompt>,
+}
+
+impl Editor {
+    pub fn new(size: &Size2D) -> Result<Self, GenerationError> {
+        Ok(Self {
+            field: Minefield::new_empty(size)?,
+            cursor: Point2D::default(),
+            cursor_repeat: Default::default(),
+            viewport: Point2D::default(),
+            status: None,
+            export_prompt: None,
+        })
+    }
+
+    fn toggle(&mut self) {
+        let location = self.cursor.clone();
+        self.field.toggle_mine(&location);
+    }
+
+    fn export(&mut self, path: &str) {
+        let status = match fs::write(path, self.field.export()) {
+            Ok(()) => format!("Saved {}", path),
+            Err(error) => format!("Export failed: {}", error),
+        };
+        self.status = Some(status);
+    }
+
+    /// Handles a frame of typing the export path. Existing files are only replaced after
+    /// confirming with y, any other key goes back to editing the path.
+    fn update_export_prompt(&mut self, input: &dyn Input) {
+        let Some(prompt) = self.export_prompt.as_mut() else { return };
+        if prompt.confirm_overwrite {
+            if input.is_key_pressed(KEY_CONFIRM_OVERWRITE) {
+                let path = prompt.path.clone();
+                self.export_prompt = None;
+                self.export(&path);
+            } else if input.is_any_key_pressed() {
+                prompt.confirm_overwrite = false;
+            }
+            return;
+        }
+        if input.is_key_pressed(KeyCode::Esc) {
+            self.export_prompt = None;
+        } else if input.is_key_pressed(KeyCode::Enter) && !prompt.path.is_empty() {
+            if Path::new(&prompt.path).exists() {
+                prompt.confirm_overwrite = true;
+            } else {
+                let path = prompt.path.clone();
+                self.export_prompt = None;
+                self.export(&path);
+            }
+        } else if input.is_key_pressed(KeyCode::Backspace) {
+            prompt.path.pop();
+        } else if let Some(typed) = typed_char(input) {
+            prompt.path.push(typed);
+        }
+    }
+
+    /// Reports how much guessing the board takes when started at the cursor.
+    fn analyze(&mut self) {
+        let status = match self.field.analyze(&self.cursor) {
+            None => String::from("Start the analysis on water"),
+            Some(analysis) if analysis.solvable() => String::from("Solvable without guessing"),
+            Some(analysis) => format!("Guesses: {} (50/50s: {})", analysis.guesses, analysis.fifty_fifties),
+        };
+        self.status = Some(status);
+    }
+
+    fn play(&self) -> Option<SystemEvent> {
+        let settings = GameSettings {
+            size: self.field.size().clone(),
+            mine_count: self.field.mine_count(),
+            ..GameSettings::default()
+        };
+        let game = Game::with_settings(self.field.clone(), settings);
+        Some(SystemEvent::ChangeState(Box::new(game)))
+    }
+}
+
+impl GameState for Editor {
+    fn update(&mut self, input: &dyn Input, _dt: Duration) -> Option<SystemEvent> {
+        if self.export_prompt.is_some() {
+            self.update_export_prompt(input);
+            return None;
+        }
+        if input.is_key_pressed(KeyCode::Esc) {
+            return Some(SystemEvent::Exit);
+        }
+        if input.is_key_pressed(KEY_MENU) {
+            return Some(SystemEvent::ChangeState(Box::<MainMenu>::default()));
+        }
+        if input.is_key_pressed(KEY_PLAY) {
+            return self.play();
+        }
+        move_cursor(&mut self.cursor, &mut self.cursor_repeat, input, self.field.size());
+        let view = Size2D(input.get_width() as usize, input.get_height() as usize);
+        self.viewport = self.field.scroll_to(&self.viewport, &self.cursor, &view);
+        if input.is_key_pressed(KEY_TOGGLE) {
+            self.toggle();
+        }
+        if input.is_key_pressed(KEY_EXPORT) {
+            self.export_prompt = Some(ExportPrompt {
+                path: String::from(EXPORT_PATH),
+                confirm_overwrite: false,
+            });
+            self.status = None;
+        }
+        if input.is_key_pressed(KEY_ANALYZE) {
+            self.analyze();
+        }
+        None
+    }
+
+    fn draw(&self, screen: &mut Screen) {
+        let field_screen = self.field.draw_layout();
+        let offset_x = field_offset(field_screen.get_width(), screen.get_width(), self.viewport.0);
+        let offset_y = field_offset(field_screen.get_height(), screen.get_height(), self.viewport.1);
+        screen.print_screen(offset_x, offset_y, &field_screen);
+        let cursor_x = self.field.screen_column(self.cursor.0) as i32 + offset_x;
+        let cursor_y = self.field.screen_row(self.cursor.1) as i32 + offset_y;
+        screen.set_pxl(cursor_x - 1, cursor_y, pxl('['));
+        screen.set_pxl(cursor_x + 1, cursor_y, pxl(']'));
+        screen.print(0, 0, &format!("Mines: {}", self.field.mine_count()));
+        let bottom = screen.get_height() as i32 - 1;
+        match (&self.export_prompt, &self.status) {
+            (Some(prompt), _) if prompt.confirm_overwrite => screen.print(0, bottom, &format!("{} exists, overwrite? y/n", prompt.path)),
+            (Some(prompt), _) => screen.print(0, bottom, &format!("Export to: {}", prompt.path)),
+            (None, Some(status)) => screen.print(0, bottom, status),
+            (None, None) => screen.print(0, bottom, "Space: Mine  X: Export  A: Analyze  Enter: Play"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use console_engine::KeyCode;
+    use crate::editor::Editor;
+    use crate::geom::{Point2D, Size2D};
+    use crate::input::MockInput;
+    use crate::state::GameState;
+
+    #[test]
+    fn toggle_twice_restores_water() {
+        let mut editor = Editor::new(&Size2D(3, 3)).unwrap();
+        editor.cursor = Point2D(1, 2);
+        editor.toggle();
+        assert!(editor.field.is_mine(&Point2D(1, 2)));
+        assert_eq!(1, editor.field.mine_count());
+        editor.toggle();
+        assert!(!editor.field.is_mine(&Point2D(1, 2)));
+        assert_eq!(0, editor.field.mine_count());
+    }
+
+    #[test]
+    fn analyze_reports_guesses() {
+        let mut editor = Editor::new(&Size2D(3, 2)).unwrap();
+        editor.toggle();
+        editor.cursor = Point2D(2, 1);
+        editor.analyze();
+        assert_eq!(Some("Guesses: 1 (50/50s: 1)"), editor.status.as_deref());
+    }
+
+    #[test]
+    fn export_shows_mines() {
+        let mut editor = Editor::new(&Size2D(3, 2)).unwrap();
+        editor.cursor = Point2D(2, 0);
+        editor.toggle();
+        assert_eq!("..*\n...", editor.field.export());
+    }
+
+    #[test]
+    fn export_asks_before_overwriting() {
+        let path = std::env::temp_dir().join(format!("bombe-export-{}.txt", std::process::id()));
+        fs::write(&path, "keep").unwrap();
+        let mut editor = Editor::new(&Size2D(2, 1)).unwrap();
+        editor.toggle();
+        let mut press = |key| editor.update(&MockInput::pressing(key), Default::default());
+        press(KeyCode::Char('x'));
+        (0..10).for_each(|_| {
+            press(KeyCode::Backspace);
+        });
+        path.to_str().unwrap().chars().for_each(|c| {
+            press(KeyCode::Char(c));
+        });
+        press(KeyCode::Enter);
+        press(KeyCode::Char('n'));
+        assert_eq!("keep", fs::read_to_string(&path).unwrap());
+        press(KeyCode::Enter);
+        press(KeyCode::Char('y'));
+        assert_eq!("*.", fs::read_to_string(&path).unwrap());
+        assert!(editor.export_prompt.is_none());
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,20 @@
+use crate::geom::Point2D;
+
+/// Hooks for effects such as sounds on notable moments of a game. Every method does nothing unless overridden.
+pub trait EventSink {
+    /// A closed cell at `location` was opened by the player.
+    fn on_open(&mut self, _location: &Point2D) {}
+
+    /// A flag was placed on or removed from `location`.
+    fn on_flag(&mut self, _location: &Point2D) {}
+
+    fn on_win(&mut self) {}
+
+    fn on_loss(&mut self) {}
+}
+
+/// Ignores all events.
+#[derive(Debug, Default)]
+pub struct NoEvents;
+
+impl EventSink for NoEvents {}
@@ -1,25 +1,56 @@
-use console_engine::{Color, ConsoleEngine, KeyCode};
+use std::cmp::{max, min, Reverse};
+use std::collections::VecDeque;
+use std::{fmt, fs};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use console_engine::{Color, KeyCode, KeyEventKind, KeyModifiers};
 use console_engine::pixel::{Pixel, pxl, pxl_fbg, pxl_fg};
+use console_engine::rect_style::BorderStyle;
 use console_engine::screen::Screen;
-use itertools::Itertools;
-use rand::Rng;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use crate::bindings::{key_name, Action, KeyBindings};
 use crate::collections::Vec2D;
-use crate::geom::{Point2D, Size2D};
+use crate::events::{EventSink, NoEvents};
+use crate::geom::{grid_column, grid_width, Direction, Point2D, Size2D};
+use crate::input::Input;
+use crate::main_menu::MainMenu;
+use crate::render::{Renderer, ScreenRenderer};
+use crate::replay::{MoveKind, MoveLog};
+use crate::share::{copy_to_clipboard, ShareCode};
 use crate::state::{GameState, SystemEvent};
+use crate::stats::{HighScores, StatsScreen};
 
-const KEY_OPEN: KeyCode = KeyCode::Char(' ');
-const KEY_FLAG: KeyCode = KeyCode::Char('f');
+const UNDO_LIMIT: usize = 100;
+/// Rows of the screen taken by the lives counter and the status line.
+const STATUS_ROWS: usize = 2;
+const SHARE_PATH: &str = "share.txt";
+const KEY_CONFIRM_QUIT: KeyCode = KeyCode::Char('y');
+const EXPLOSION_FRAMES: u32 = 8;
+const EXPLOSION_FRAME_TIME: Duration = Duration::from_millis(67);
+const MAX_GENERATION_ATTEMPTS: usize = 100;
+const REPEAT_DELAY_FRAMES: usize = 6;
+const REPEAT_INTERVAL_FRAMES: usize = 2;
+/// Arrow keys in the order of the cursor repeat timers.
+const ARROW_KEYS: [(KeyCode, Direction); 4] = [
+    (KeyCode::Left, Direction::W),
+    (KeyCode::Up, Direction::N),
+    (KeyCode::Right, Direction::E),
+    (KeyCode::Down, Direction::S),
+];
+/// Digits of the layout codes, 6 bits each.
+const LAYOUT_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+const HEAT_COLORS: [Color; 6] = [Color::DarkGreen, Color::Green, Color::Yellow, Color::DarkYellow, Color::Red, Color::DarkRed];
 const NUMBER_COLORS: [Color; 6] = [Color::Cyan, Color::DarkCyan, Color::Yellow, Color::DarkYellow, Color::Magenta, Color::Red];
 
 #[derive(Debug, Copy, Clone, PartialEq, Default)]
-enum CellType {
+pub enum CellType {
     #[default]
     Water,
     Mine,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Default)]
-enum CellState {
+pub enum CellState {
     #[default]
     Closed,
     Flagged,
@@ -61,304 +92,4147 @@ impl Cell {
     fn flag(&mut self) {
         self.state = self.state.toggle_flag();
     }
+
+    /// Water that still has to be opened to win.
+    fn is_closed_water(&self) -> bool {
+        !self.is_open() && self.cell_type == CellType::Water
+    }
+}
+
+/// Outcome of trying to open a single cell.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum OpenResult {
+    Water,
+    Mine,
+    /// Nothing happened as the cell is flagged.
+    Flagged,
+    AlreadyOpen,
+    /// The location is off the board or masked out.
+    OutOfBounds,
+}
+
+/// Outcome of toggling the flag on a single cell.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FlagResult {
+    Added,
+    Removed,
+    /// Nothing happened as the cell is open, off the board or masked out.
+    NoOp,
+}
+
+impl OpenResult {
+    fn opened_type(&self) -> Option<CellType> {
+        match self {
+            OpenResult::Water => Some(CellType::Water),
+            OpenResult::Mine => Some(CellType::Mine),
+            _ => None,
+        }
+    }
+}
+
+/// Read-only snapshot of a single cell for code outside this module.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CellView {
+    pub cell_type: CellType,
+    pub state: CellState,
+}
+
+impl From<&Cell> for CellView {
+    fn from(cell: &Cell) -> Self {
+        Self {
+            cell_type: cell.cell_type,
+            state: cell.state,
+        }
+    }
+}
+
+/// Debug glyph of a single cell. Opened water shows `o` as its number depends on the neighbours.
+impl fmt::Display for Cell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let glyph = match (self.state, self.cell_type) {
+            (CellState::Closed, _) => '.',
+            (CellState::Flagged, _) => 'F',
+            (CellState::Opened, CellType::Mine) => '*',
+            (CellState::Opened, CellType::Water) => 'o',
+        };
+        write!(f, "{}", glyph)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+enum CellSpacing {
+    #[default]
+    Wide,
+    Compact,
+}
+
+impl CellSpacing {
+    fn columns(&self) -> usize {
+        match self {
+            CellSpacing::Wide => 2,
+            CellSpacing::Compact => 1,
+        }
+    }
+
+    fn toggle(&self) -> CellSpacing {
+        match self {
+            CellSpacing::Wide => CellSpacing::Compact,
+            CellSpacing::Compact => CellSpacing::Wide,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+enum CursorStyle {
+    #[default]
+    Brackets,
+    Reverse,
+}
+
+impl CursorStyle {
+    fn toggle(&self) -> CursorStyle {
+        match self {
+            CursorStyle::Brackets => CursorStyle::Reverse,
+            CursorStyle::Reverse => CursorStyle::Brackets,
+        }
+    }
+}
+
+/// What the open key does, so that a single key can play the whole game.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum InputMode {
+    #[default]
+    Open,
+    Flag,
+}
+
+impl InputMode {
+    fn toggle(&self) -> InputMode {
+        match self {
+            InputMode::Open => InputMode::Flag,
+            InputMode::Flag => InputMode::Open,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            InputMode::Open => "open",
+            InputMode::Flag => "flag",
+        }
+    }
+
+    /// Replaces the open action by the one of this mode.
+    fn apply(&self, pressed: Vec<Action>) -> Vec<Action> {
+        if *self == InputMode::Open || !pressed.contains(&Action::Open) {
+            return pressed;
+        }
+        let mut actions = vec![Action::Flag];
+        actions.extend(pressed.into_iter().filter(|action| !matches!(action, Action::Open | Action::Flag)));
+        actions
+    }
+}
+
+/// Extra information drawn on top of what the player knows.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct DrawMarks {
+    /// Tints closed mines, see `Game::toggle_reveal_hint`.
+    pub mine_hint: bool,
+    /// Crosses out flags placed on water, for looking back at a lost game.
+    pub wrong_flags: bool,
+    /// Dimly shows the number of every closed water cell and marks closed mines.
+    pub peek: bool,
+}
+
+/// Characters drawn for the cell states that don't show a number.
+/// Wide characters like emoji work best with the wide cell spacing.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Glyphs {
+    pub closed: char,
+    pub flag: char,
+    pub mine: char,
+    /// Opened water without adjacent mines.
+    pub empty: char,
+    pub empty_color: Color,
+}
+
+impl Default for Glyphs {
+    fn default() -> Self {
+        Self {
+            closed: '?',
+            flag: 'F',
+            mine: 'M',
+            empty: ' ',
+            empty_color: Color::Reset,
+        }
+    }
+}
+
+impl Glyphs {
+    /// Marks opened empty cells with a faint dot, so cleared areas stand out from the background.
+    pub fn dotted() -> Self {
+        Self {
+            empty: '·',
+            empty_color: Color::DarkGrey,
+            ..Self::default()
+        }
+    }
+}
+
+/// How the edges of the board connect.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum Topology {
+    #[default]
+    Flat,
+    /// Opposite edges are adjacent, so every cell has 8 neighbours.
+    Torus,
+}
+
+/// What is uncovered when a game is lost.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum LossReveal {
+    #[default]
+    All,
+    Mines,
+    Nothing,
+}
+
+impl LossReveal {
+    fn cycle(&self) -> LossReveal {
+        match self {
+            LossReveal::All => LossReveal::Mines,
+            LossReveal::Mines => LossReveal::Nothing,
+            LossReveal::Nothing => LossReveal::All,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            LossReveal::All => "all",
+            LossReveal::Mines => "mines",
+            LossReveal::Nothing => "nothing",
+        }
+    }
+}
+
+/// Reverse video version of a drawn cell, keeping its glyph.
+fn reverse_pixel(pixel: Pixel) -> Pixel {
+    let bg = if pixel.fg == Color::Reset { Color::White } else { pixel.fg };
+    pxl_fbg(pixel.chr, Color::Black, bg)
+}
+
+/// Screen column of the cell at board column `x`, leaving room for the cursor brackets.
+fn cell_column(x: usize, spacing: CellSpacing) -> usize {
+    grid_column(x, spacing.columns())
+}
+
+fn field_width(width: usize, spacing: CellSpacing) -> usize {
+    grid_width(width, spacing.columns())
+}
+
+/// Box-drawing frame along the edges of an area of `size`.
+fn draw_frame(renderer: &mut impl Renderer, size: &Size2D) {
+    let mut line = |col, row, glyph| renderer.draw_cell(col, row, glyph, Color::Reset, Color::Reset);
+    let right = size.0 - 1;
+    let bottom = size.1 - 1;
+    (1..right).for_each(|x| {
+        line(x, 0, '─');
+        line(x, bottom, '─');
+    });
+    (1..bottom).for_each(|y| {
+        line(0, y, '│');
+        line(right, y, '│');
+    });
+    line(0, 0, '┌');
+    line(right, 0, '┐');
+    line(0, bottom, '└');
+    line(right, bottom, '┘');
+}
+
+/// Scrolls a single axis of the viewport so that `start..=end` stays visible.
+fn scroll_axis(offset: usize, start: usize, end: usize, field_len: usize, view_len: usize) -> usize {
+    if field_len <= view_len {
+        return 0;
+    }
+    let offset = if start < offset {
+        start
+    } else if end >= offset + view_len {
+        end + 1 - view_len
+    } else {
+        offset
+    };
+    min(offset, field_len - view_len)
+}
+
+/// Screen offset of one axis of the field: centered if it fits, scrolled otherwise.
+pub fn field_offset(field_len: u32, view_len: u32, viewport: usize) -> i32 {
+    if field_len <= view_len {
+        (view_len / 2 - field_len / 2) as i32
+    } else {
+        -(viewport as i32)
+    }
+}
+
+/// Largest board that fits on a screen of `view` without scrolling, keeping the
+/// top and bottom rows free for the status lines.
+pub fn fitting_board_size(view: &Size2D) -> Size2D {
+    let board_view = Size2D(view.0, view.1.saturating_sub(STATUS_ROWS));
+    let columns = CellSpacing::default().columns();
+    let width = view.0.saturating_sub(grid_width(1, columns)) / columns + 1;
+    Size2D(width, max(board_view.1, 1))
 }
 
+#[derive(Clone)]
 pub struct Minefield {
     data: Vec2D<Cell>,
+    /// Marks which cells are playable. Masked-out cells behave as if they were off the board.
+    mask: Option<Vec2D<bool>>,
+    spacing: CellSpacing,
+    /// Draws a box around the cells.
+    border: bool,
+    /// Opens the neighbours of cells without adjacent mines along with them.
+    flood: bool,
+    /// Stops flood fills at flagged cells instead of opening them.
+    flood_respects_flags: bool,
+    /// Cells changed since the last redraw.
+    dirty: Vec<Point2D>,
+    topology: Topology,
+    glyphs: Glyphs,
+    /// Number of closed water cells, updated along with every change so checking for a win needs no scan.
+    safe_remaining: usize,
 }
 
 impl Minefield {
     fn with_data(data: Vec2D<Cell>) -> Self {
-        Self {
+        let mut field = Self {
             data,
+            mask: None,
+            spacing: CellSpacing::default(),
+            border: false,
+            flood: true,
+            flood_respects_flags: true,
+            dirty: Vec::new(),
+            topology: Topology::default(),
+            glyphs: Glyphs::default(),
+            safe_remaining: 0,
+        };
+        field.recount_safe_remaining();
+        field
+    }
+
+    /// A closed board without mines, failing with `EmptySize` if a side is zero.
+    pub fn new_empty(size: &Size2D) -> Result<Self, GenerationError> {
+        validate_size(size)?;
+        Ok(Self::with_data(Vec2D::sized(size, Cell::default())))
+    }
+
+    fn with_mask(data: Vec2D<Cell>, mask: Vec2D<bool>) -> Self {
+        let mut field = Self {
+            mask: Some(mask),
+            ..Self::with_data(data)
+        };
+        field.recount_safe_remaining();
+        field
+    }
+
+    fn recount_safe_remaining(&mut self) {
+        self.safe_remaining = self.count(Cell::is_closed_water);
+    }
+
+    /// Closed water cells left to open. The game is won once this reaches zero.
+    pub fn remaining_safe_cells(&self) -> usize {
+        self.safe_remaining
+    }
+
+    /// Locations adjacent to `location` according to the board's topology.
+    fn neighbours_of(&self, location: &Point2D) -> Vec<Point2D> {
+        match self.topology {
+            Topology::Flat => location.neighbours(),
+            Topology::Torus => location.wrapped_neighbours(self.size()),
         }
     }
 
-    fn size(&self) -> &Size2D {
+    fn is_playable(&self, location: &Point2D) -> bool {
+        is_unmasked(self.mask.as_ref(), location)
+    }
+
+    pub fn size(&self) -> &Size2D {
         &self.data.size
     }
 
+    /// The cell at `location`, or `None` if it is out of bounds or masked out.
+    pub fn cell_at(&self, location: &Point2D) -> Option<CellView> {
+        self.get(location).map(CellView::from)
+    }
+
+    /// All playable cells with their locations, column by column.
+    pub fn cells(&self) -> impl Iterator<Item = (Point2D, CellView)> + '_ {
+        self.data.size.iter_points()
+            .filter_map(|location| self.cell_at(&location).map(|cell| (location, cell)))
+    }
+
     fn get(&self, location: &Point2D) -> Option<&Cell> {
+        if !self.is_playable(location) {
+            return None;
+        }
         self.data.get(location)
     }
 
     fn get_mut(&mut self, location: &Point2D) -> Option<&mut Cell> {
+        if !self.is_playable(location) {
+            return None;
+        }
         self.data.get_mut(location)
     }
 
     fn count_neighbours(&self, location: &Point2D) -> u8 {
-        let neighbours = location.neighbours();
+        let neighbours = self.neighbours_of(location);
         neighbours.iter()
             .filter_map(|point| self.get(point))
             .filter(|cell| cell.cell_type.eq(&CellType::Mine))
             .count() as u8
     }
 
-    fn open(&mut self, location: &Point2D) -> Option<CellType> {
-        let mut opened_type = None;
+    fn count_flagged_neighbours(&self, location: &Point2D) -> u8 {
+        self.neighbours_of(location).iter()
+            .filter_map(|point| self.get(point))
+            .filter(|cell| cell.state == CellState::Flagged)
+            .count() as u8
+    }
 
-        if let Some(cell) = self.get_mut(location) {
-            let opened = !cell.is_open();
-            opened_type = if opened { Some(cell.cell_type) } else { None };
-            cell.open();
-            if !cell.is_open() {
-                opened_type = None;
-            }
+    fn open(&mut self, location: &Point2D) -> OpenResult {
+        let Some(cell) = self.get_mut(location) else { return OpenResult::OutOfBounds };
+        let result = match (cell.state, cell.cell_type) {
+            (CellState::Opened, _) => return OpenResult::AlreadyOpen,
+            (CellState::Flagged, _) => return OpenResult::Flagged,
+            (CellState::Closed, CellType::Water) => OpenResult::Water,
+            (CellState::Closed, CellType::Mine) => OpenResult::Mine,
         };
-        let opened_water = opened_type.eq(&Some(CellType::Water));
-        let no_neighbouring_mines = self.count_neighbours(location) == 0;
-        if opened_water && no_neighbouring_mines {
-            location.neighbours().iter().for_each(|neighbour| {
+        cell.open();
+        self.dirty.push(location.clone());
+        if result == OpenResult::Water {
+            self.safe_remaining -= 1;
+        }
+        if self.flood && result == OpenResult::Water && self.count_neighbours(location) == 0 {
+            self.neighbours_of(location).iter().for_each(|neighbour| {
+                if !self.flood_respects_flags {
+                    if let Some(cell) = self.get_mut(neighbour).filter(|cell| cell.state == CellState::Flagged) {
+                        cell.state = CellState::Closed;
+                    }
+                }
                 self.open(neighbour);
             });
         }
-        opened_type
+        result
     }
 
-    fn flag(&mut self, location: &Point2D) {
-        if let Some(cell) = self.get_mut(location) {
-            cell.flag();
-        }
+    pub fn flag(&mut self, location: &Point2D) -> FlagResult {
+        let Some(cell) = self.get_mut(location) else {
+            return FlagResult::NoOp;
+        };
+        let state = cell.state;
+        cell.flag();
+        let result = match (state, cell.state) {
+            (CellState::Closed, CellState::Flagged) => FlagResult::Added,
+            (CellState::Flagged, CellState::Closed) => FlagResult::Removed,
+            _ => return FlagResult::NoOp,
+        };
+        self.dirty.push(location.clone());
+        result
     }
 
-    fn draw(&self) -> Screen {
-        let mut screen = Screen::new_fill(self.size().0 as u32 * 2 - 1, self.size().1 as u32, pxl(' '));
-        self.data.all_locations().into_iter()
-            .for_each(|location| {
-                self.draw_cell(&location, &mut screen);
-            });
-        screen
+    /// Locations whose drawing changed since the last call, for incremental redraws.
+    fn take_dirty(&mut self) -> Vec<Point2D> {
+        std::mem::take(&mut self.dirty)
     }
 
-    fn draw_cell(&self, location: &Point2D, screen: &mut Screen) {
-        if let Some(cell) = self.get(location) {
-            let pixel = self.pixel_for_cell(location, cell);
-            screen.set_pxl((location.0 * 2 + 1) as i32, location.1 as i32, pixel);
+    /// Opens all closed neighbours of an opened number once it is surrounded by as many flags.
+    fn chord(&mut self, location: &Point2D) -> Option<CellType> {
+        let cell = self.get(location)?;
+        if !cell.is_open() || cell.cell_type != CellType::Water {
+            return None;
         }
-    }
-
-    fn pixel_for_cell(&self, location: &Point2D, cell: &Cell) -> Pixel {
-        match cell.state {
-            CellState::Closed => pxl('?'),
-            CellState::Flagged => pxl_fbg('F', Color::White, Color::DarkGreen),
-            CellState::Opened => self.pixel_for_open_cell(location, cell)
+        if self.known_mines(location) != self.count_neighbours(location) as usize {
+            return None;
+        }
+        let opened = self.neighbours_in_state(location, CellState::Closed).iter()
+            .filter_map(|neighbour| self.open(neighbour).opened_type())
+            .collect::<Vec<_>>();
+        if opened.contains(&CellType::Mine) {
+            Some(CellType::Mine)
+        } else {
+            opened.first().copied()
         }
     }
 
-    fn pixel_for_open_cell(&self, location: &Point2D, cell: &Cell) -> Pixel {
-        match cell.cell_type {
-            CellType::Mine => pxl_fbg('M', Color::White, Color::DarkRed),
-            CellType::Water => match self.count_neighbours(location) {
-                0 => pxl(' '),
-                num => pxl_fg((num + 0x30) as char, color_for_number(num)),
-            }
+    /// Flags all closed neighbours of an opened number if they must all be mines,
+    /// returning how many were flagged.
+    fn auto_flag(&mut self, location: &Point2D) -> usize {
+        let Some(cell) = self.get(location) else { return 0 };
+        if !cell.is_open() || cell.cell_type != CellType::Water {
+            return 0;
         }
+        let closed = self.neighbours_in_state(location, CellState::Closed);
+        if closed.len() + self.known_mines(location) != self.count_neighbours(location) as usize {
+            return 0;
+        }
+        closed.iter().for_each(|neighbour| {
+            self.flag(neighbour);
+        });
+        closed.len()
     }
 
-    fn reveal_all(&mut self) {
-        self.data.all_locations().into_iter()
-            .for_each(|location| {
-                self.open(&location);
-            });
+    /// Neighbours that are neither opened nor flagged, which a chord would open.
+    pub fn closed_unflagged_neighbours(&self, location: &Point2D) -> Vec<Point2D> {
+        self.neighbours_in_state(location, CellState::Closed)
     }
 
-    fn only_mines_remaining(&self) -> bool {
-        (0..self.size().0).cartesian_product(0..self.size().1)
-            .filter_map(|(x, y)| self.get(&Point2D(x, y)))
-            .filter(|cell| !cell.is_open() && cell.cell_type == CellType::Water)
-            .count() == 0
+    fn neighbours_in_state(&self, location: &Point2D, state: CellState) -> Vec<Point2D> {
+        self.neighbours_of(location).into_iter()
+            .filter(|neighbour| self.get(neighbour).is_some_and(|cell| cell.state == state))
+            .collect()
     }
-}
 
-pub struct RandomMineFieldGenerator<T> where T: Rng {
-    pub random: T
-}
+    /// Neighbours known to be mines: flagged cells and mines already opened in lives mode.
+    fn known_mines(&self, location: &Point2D) -> usize {
+        let opened_mines = self.neighbours_of(location).iter()
+            .filter_map(|neighbour| self.get(neighbour))
+            .filter(|cell| cell.is_open() && cell.cell_type == CellType::Mine)
+            .count();
+        self.count_flagged_neighbours(location) as usize + opened_mines
+    }
 
-impl<T> RandomMineFieldGenerator<T> where T: Rng {
-    pub fn generate(&mut self, size: Size2D, mine_count: usize) -> Minefield {
-        if size.0 * size.1 < mine_count {
-            panic!("Cannot place more mines than there are cells!");
-        }
-        let mut cells = Vec2D::sized(&size, Cell::default());
-        let mut mines_placed = 0;
-        loop {
-            let new_location = Point2D(self.random.gen_range(0..size.0), self.random.gen_range(0..size.1));
-            let cell = cells.get_mut(&new_location).unwrap();
-            if cell.cell_type == CellType::Water {
-                cell.cell_type = CellType::Mine;
-                mines_placed += 1;
-                if mines_placed == mine_count {
-                    break;
-                }
+    /// Applies one round of the basic deductions to every opened number and
+    /// returns whether any cell was flagged or opened.
+    fn solve_step(&mut self) -> bool {
+        let mut progress = false;
+        for location in self.data.size.iter_points() {
+            let Some(cell) = self.get(&location) else { continue };
+            if !cell.is_open() || cell.cell_type != CellType::Water {
+                continue;
+            }
+            let value = self.count_neighbours(&location) as usize;
+            let closed = self.neighbours_in_state(&location, CellState::Closed);
+            if closed.is_empty() {
+                continue;
+            }
+            let flagged = self.known_mines(&location);
+            if closed.len() + flagged == value {
+                self.auto_flag(&location);
+                progress = true;
+            } else if flagged == value {
+                closed.iter().for_each(|neighbour| {
+                    self.open(neighbour);
+                });
+                progress = true;
             }
         }
-        Minefield::with_data(cells)
+        progress
     }
-}
 
-fn color_for_number(number: u8) -> Color {
-    NUMBER_COLORS.get(number as usize).cloned().unwrap_or(Color::White)
-}
-
-pub struct Game {
-    field: Minefield,
-    cursor: Point2D,
-    game_over: bool,
-    won: bool,
-}
+    /// Repeats `solve_step` until it gets stuck, leaving every cell that needs a guess closed.
+    fn sweep(&mut self) -> bool {
+        let mut progress = false;
+        while self.solve_step() {
+            progress = true;
+        }
+        progress
+    }
 
-impl Game {
-    pub fn with_minefield(field: Minefield) -> Self {
-        Self {
-            field,
-            cursor: Point2D::default(),
-            game_over: false,
-            won: false,
+    /// Pairs of closed cells sharing a single remaining mine of an opened number,
+    /// found only once the basic deductions have nothing left to offer.
+    fn fifty_fifties(&self) -> Vec<(Point2D, Point2D)> {
+        if self.clone().solve_step() {
+            return Vec::new();
         }
+        self.data.size.iter_points()
+            .filter(|location| self.get(location).is_some_and(|cell| cell.is_open() && cell.cell_type == CellType::Water))
+            .filter_map(|location| {
+                let remaining = (self.count_neighbours(&location) as usize).saturating_sub(self.known_mines(&location));
+                match self.neighbours_in_state(&location, CellState::Closed).as_slice() {
+                    [a, b] if remaining == 1 => Some((a.clone(), b.clone())),
+                    _ => None,
+                }
+            })
+            .collect()
     }
 
-    fn move_cursor(&mut self, engine: &ConsoleEngine) {
-        if engine.is_key_pressed(KeyCode::Left) && self.cursor.0 > 0 {
-            self.cursor.0 -= 1;
+    /// Plays the board from a closed state starting at `start`, guessing a safe cell whenever
+    /// the basic deductions get stuck, and counts those guesses. `None` if `start` is a mine.
+    pub fn analyze(&self, start: &Point2D) -> Option<BoardAnalysis> {
+        if self.get(start)?.cell_type == CellType::Mine {
+            return None;
         }
-        if engine.is_key_pressed(KeyCode::Up) && self.cursor.1 > 0 {
-            self.cursor.1 -= 1;
+        let mut field = self.clone();
+        let closed = field.cell_states().into_iter()
+            .map(|(location, _)| (location, CellState::Closed))
+            .collect::<Vec<_>>();
+        field.restore(&closed);
+        field.open(start);
+        let mut analysis = BoardAnalysis::default();
+        loop {
+            while field.solve_step() {}
+            if field.only_mines_remaining() {
+                return Some(analysis);
+            }
+            let pairs = field.fifty_fifties();
+            analysis.guesses += 1;
+            analysis.fifty_fifties += !pairs.is_empty() as usize;
+            let guess = pairs.into_iter()
+                .flat_map(|(a, b)| [a, b])
+                .find(|location| !field.is_mine(location))
+                .or_else(|| field.data.size.iter_points().find(|location| field.get(location).is_some_and(Cell::is_closed_water)))?;
+            field.open(&guess);
         }
-        if engine.is_key_pressed(KeyCode::Right) {
-            self.cursor.0 += 1;
+    }
+
+    fn opened_numbers(&self) -> Vec<u8> {
+        self.data.size.iter_points()
+            .filter(|location| self.get(location).is_some_and(|cell| cell.is_open() && cell.cell_type == CellType::Water))
+            .map(|location| self.count_neighbours(&location))
+            .collect()
+    }
+
+    /// Moves the mine at `from` onto the closed water cell `to`, unless that would
+    /// change any number the player has already seen.
+    fn relocate_mine(&mut self, from: &Point2D, to: &Point2D) -> bool {
+        let movable = self.is_mine(from)
+            && self.get(to).is_some_and(|cell| cell.cell_type == CellType::Water && cell.state == CellState::Closed);
+        if !movable {
+            return false;
         }
-        if engine.is_key_pressed(KeyCode::Down) {
-            self.cursor.1 += 1;
+        let numbers = self.opened_numbers();
+        self.set_type(from, CellType::Water);
+        self.set_type(to, CellType::Mine);
+        if self.opened_numbers() == numbers {
+            return true;
         }
-        self.cursor.clip_excl(self.field.size());
+        self.set_type(from, CellType::Mine);
+        self.set_type(to, CellType::Water);
+        false
     }
-}
 
-impl GameState for Game {
+    /// Naive per-cell mine probability for closed cells, averaging the remaining-mines
+    /// ratio of every neighbouring opened number. Closed cells without any such number
+    /// get the overall density of the unflagged mines. Other cells are 0.
+    fn mine_probability(&self) -> Vec2D<f64> {
+        let closed_count = self.count_in_state(CellState::Closed);
+        let remaining_mines = self.mine_count()
+            .saturating_sub(self.count_in_state(CellState::Flagged) + self.opened_mine_count());
+        let density = if closed_count == 0 { 0.0 } else { remaining_mines as f64 / closed_count as f64 };
+        Vec2D::from_fn(self.size(), |location| {
+            if !self.get(location).is_some_and(|cell| cell.state == CellState::Closed) {
+                return 0.0;
+            }
+            let estimates = self.neighbours_of(location).iter()
+                .filter(|neighbour| self.get(neighbour).is_some_and(|cell| cell.is_open() && cell.cell_type == CellType::Water))
+                .map(|neighbour| {
+                    let remaining = (self.count_neighbours(neighbour) as usize).saturating_sub(self.known_mines(neighbour));
+                    remaining as f64 / self.neighbours_in_state(neighbour, CellState::Closed).len() as f64
+                })
+                .collect::<Vec<_>>();
+            if estimates.is_empty() {
+                density
+            } else {
+                estimates.iter().sum::<f64>() / estimates.len() as f64
+            }
+        })
+    }
 
-    fn update(&mut self, engine: &ConsoleEngine) -> Option<SystemEvent> {
-        if engine.is_key_pressed(KeyCode::Esc) {
-            return Some(SystemEvent::Exit);
-        }
-        if self.game_over || self.won {
-            return None;
+    /// A random mine that is neither opened nor flagged.
+    fn random_closed_mine(&self, random: &mut impl Rng) -> Option<Point2D> {
+        let mines = self.data.size.iter_points()
+            .filter(|location| self.get(location).is_some_and(|cell| cell.cell_type == CellType::Mine && cell.state == CellState::Closed))
+            .collect::<Vec<_>>();
+        (!mines.is_empty()).then(|| mines[random.gen_range(0..mines.len())].clone())
+    }
+
+    /// Opens up to `count` randomly chosen closed water cells, each with its flood region,
+    /// and returns the chosen cells. Mines are never touched.
+    fn open_random_safe(&mut self, count: usize, random: &mut impl Rng) -> Vec<Point2D> {
+        let mut seeds = Vec::new();
+        while seeds.len() < count {
+            let candidates = self.data.size.iter_points()
+                .filter(|location| self.get(location).is_some_and(Cell::is_closed_water))
+                .collect::<Vec<_>>();
+            if candidates.is_empty() {
+                break;
+            }
+            let seed = candidates[random.gen_range(0..candidates.len())].clone();
+            self.open(&seed);
+            seeds.push(seed);
         }
-        self.move_cursor(engine);
-        let mut opened_type = None;
-        if engine.is_key_pressed(KEY_OPEN) {
-            opened_type = self.field.open(&self.cursor);
+        seeds
+    }
+
+    /// Size of the largest 8-connected group of water cells without neighbouring mines.
+    fn largest_empty_region(&self) -> usize {
+        let is_empty = |location: &Point2D| self.get(location)
+            .is_some_and(|cell| cell.cell_type == CellType::Water && self.count_neighbours(location) == 0);
+        let mut visited = Vec2D::sized(self.size(), false);
+        let mut largest = 0;
+        for start in self.data.size.iter_points() {
+            if visited.get(&start) == Some(&true) || !is_empty(&start) {
+                continue;
+            }
+            let mut region_size = 0;
+            let mut pending = vec![start];
+            while let Some(location) = pending.pop() {
+                match visited.get_mut(&location) {
+                    Some(seen) if !*seen && is_empty(&location) => *seen = true,
+                    _ => continue,
+                }
+                region_size += 1;
+                pending.extend(self.neighbours_of(&location));
+            }
+            largest = max(largest, region_size);
         }
-        if engine.is_key_pressed(KEY_FLAG) {
-            self.field.flag(&self.cursor);
+        largest
+    }
+
+    /// Most cells a single click on the closed board can open, which is the largest empty
+    /// region together with the numbers around it. Numbers bordering several regions count
+    /// for each of them.
+    fn largest_opening(&self) -> usize {
+        let is_empty = |location: &Point2D| self.get(location)
+            .is_some_and(|cell| cell.cell_type == CellType::Water && self.count_neighbours(location) == 0);
+        let is_water = |location: &Point2D| self.get(location).is_some_and(|cell| cell.cell_type == CellType::Water);
+        // Id of the last region that counted each cell, so shared numbers count once per region.
+        let mut counted_by = Vec2D::sized(self.size(), None);
+        let mut largest = usize::from(self.data.size.iter_points().any(|location| is_water(&location)));
+        for (region, start) in self.data.size.iter_points().enumerate() {
+            if counted_by.get(&start) != Some(&None) || !is_empty(&start) {
+                continue;
+            }
+            let mut opening = 0;
+            let mut pending = vec![start];
+            while let Some(location) = pending.pop() {
+                match counted_by.get_mut(&location) {
+                    Some(counted) if *counted != Some(region) && is_water(&location) => *counted = Some(region),
+                    _ => continue,
+                }
+                opening += 1;
+                if is_empty(&location) {
+                    pending.extend(self.neighbours_of(&location));
+                }
+            }
+            largest = max(largest, opening);
         }
+        largest
+    }
 
-        if let Some(CellType::Mine) = opened_type {
-            self.field.reveal_all();
-            self.game_over = true;
-        } else if self.field.only_mines_remaining() {
-            self.field.reveal_all();
-            self.won = true;
+    /// Checks invariants the rest of the code relies on, listing every violation found.
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let mut violations = Vec::new();
+        if let Some(mask) = &self.mask {
+            if mask.size != *self.size() {
+                violations.push(format!("mask size {:?} differs from board size {:?}", mask.size, self.size()));
+            }
+            self.data.size.iter_points()
+                .filter(|location| !self.is_playable(location))
+                .for_each(|location| {
+                    let cell = self.data.get(&location).unwrap();
+                    if cell.cell_type == CellType::Mine {
+                        violations.push(format!("masked cell {:?} holds a mine", location));
+                    }
+                    if cell.state != CellState::Closed {
+                        violations.push(format!("masked cell {:?} is {:?}", location, cell.state));
+                    }
+                });
+        }
+        let closed_water = self.count(Cell::is_closed_water);
+        if self.safe_remaining != closed_water {
+            violations.push(format!("{} safe cells cached but {} closed water cells on the board", self.safe_remaining, closed_water));
         }
+        self.dirty.iter()
+            .filter(|location| !self.size().contains(location))
+            .for_each(|location| violations.push(format!("changed cell {:?} is off the board", location)));
+        if violations.is_empty() { Ok(()) } else { Err(violations) }
+    }
 
-        None
+    /// Number of playable cells matching `pred`.
+    fn count(&self, pred: impl Fn(&Cell) -> bool) -> usize {
+        self.data.size.iter_points()
+            .filter_map(|location| self.get(&location))
+            .filter(|cell| pred(cell))
+            .count()
     }
 
-    fn draw(&self, screen: &mut Screen) {
-        let field_screen = self.field.draw();
-        let field_offset_x = screen.get_width() / 2 - field_screen.get_width() / 2;
-        let field_offset_y = screen.get_height() / 2 - field_screen.get_height() / 2;
-        screen.print_screen(field_offset_x as i32, field_offset_y as i32, &field_screen);
-        screen.set_pxl((self.cursor.0 * 2 + field_offset_x as usize) as i32, (self.cursor.1 + field_offset_y as usize) as i32, pxl('['));
-        screen.set_pxl((self.cursor.0 * 2 + 2 + field_offset_x as usize) as i32, (self.cursor.1 + field_offset_y as usize) as i32, pxl(']'));
+    fn opened_mine_count(&self) -> usize {
+        self.count(|cell| cell.is_open() && cell.cell_type == CellType::Mine)
+    }
 
-        let message_offset_y = (field_offset_y + field_screen.get_height() + 3) as i32;
-        if self.game_over {
-            screen.print(get_message_offset_x(screen, "Game Over!"), message_offset_y, "Game Over!");
-        } else if self.won {
-            screen.print(get_message_offset_x(screen, "You Won!"), message_offset_y, "You Won!");
-        }
+    fn count_in_state(&self, state: CellState) -> usize {
+        self.count(|cell| cell.state == state)
     }
-}
 
-fn get_message_offset_x(screen: &Screen, msg: &str) -> i32 {
-    (screen.get_width() / 2 - msg.len() as u32 / 2) as i32
+    pub fn mine_count(&self) -> usize {
+        self.count(|cell| cell.cell_type == CellType::Mine)
+    }
+
+    /// Rough difficulty between 0 and 1, averaging the mine density, the mean number on
+    /// water cells scaled by 8, and the share of water cells that opening the empty areas
+    /// would not reveal. Ignores the cell states, so it only depends on the board.
+    pub fn difficulty_rating(&self) -> f64 {
+        let water = self.data.size.iter_points()
+            .filter(|location| self.get(location).is_some_and(|cell| cell.cell_type == CellType::Water))
+            .collect::<Vec<_>>();
+        let playable = water.len() + self.mine_count();
+        if water.is_empty() {
+            return if playable == 0 { 0.0 } else { 1.0 };
+        }
+        let is_empty = |location: &Point2D| !self.is_mine(location) && self.count_neighbours(location) == 0;
+        let numbers = water.iter()
+            .map(|location| self.count_neighbours(location) as usize)
+            .sum::<usize>();
+        let hidden = water.iter()
+            .filter(|location| !is_empty(location) && !self.neighbours_of(location).iter().any(is_empty))
+            .count();
+        let density = self.mine_count() as f64 / playable as f64;
+        let average_number = numbers as f64 / water.len() as f64 / 8.0;
+        let deduction = hidden as f64 / water.len() as f64;
+        (density + average_number + deduction) / 3.0
+    }
+
+    /// Whether the flags are placed on exactly the unopened mines, no more and no less.
+    fn all_mines_correctly_flagged(&self) -> bool {
+        let flags = self.count_in_state(CellState::Flagged);
+        flags + self.opened_mine_count() == self.mine_count()
+            && self.count(|cell| cell.state == CellState::Flagged && cell.cell_type == CellType::Water) == 0
+    }
+
+    fn cell_states(&self) -> Vec<(Point2D, CellState)> {
+        self.data.size.iter_points()
+            .filter_map(|location| self.get(&location).map(|cell| (location, cell.state)))
+            .collect()
+    }
+
+    fn draw(&self) -> Screen {
+        self.draw_hinted(DrawMarks::default())
+    }
+
+    /// Draws the board with the extra `marks`.
+    fn draw_hinted(&self, marks: DrawMarks) -> Screen {
+        let size = self.screen_size();
+        let mut screen = Screen::new_fill(size.0 as u32, size.1 as u32, pxl(' '));
+        self.render(&mut ScreenRenderer::new(&mut screen), marks);
+        screen
+    }
+
+    /// Draws the frame and every cell onto `renderer`, which should cover `screen_size`.
+    pub fn render(&self, renderer: &mut impl Renderer, marks: DrawMarks) {
+        if self.border {
+            draw_frame(renderer, &self.screen_size());
+        }
+        self.data.size.iter_points()
+            .for_each(|location| {
+                self.render_cell(&location, renderer, marks);
+            });
+    }
+
+    /// Redraws only `locations` onto a screen previously returned by `draw_hinted`.
+    fn redraw(&self, screen: &mut Screen, locations: &[Point2D], marks: DrawMarks) {
+        let mut renderer = ScreenRenderer::new(screen);
+        locations.iter().for_each(|location| self.render_cell(location, &mut renderer, marks));
+    }
+
+    fn render_cell(&self, location: &Point2D, renderer: &mut impl Renderer, marks: DrawMarks) {
+        if let Some(cell) = self.get(location) {
+            let pixel = self.pixel_for_cell(location, cell, marks);
+            renderer.draw_cell(self.screen_column(location.0), self.screen_row(location.1), pixel.chr, pixel.fg, pixel.bg);
+        }
+    }
+
+    /// Rows and columns taken by the border on each side.
+    fn frame_size(&self) -> usize {
+        if self.border { 1 } else { 0 }
+    }
+
+    /// Width and height of the drawn board, including the border.
+    pub fn screen_size(&self) -> Size2D {
+        let frame = self.frame_size();
+        Size2D(field_width(self.size().0, self.spacing) + 2 * frame, self.size().1 + 2 * frame)
+    }
+
+    fn pixel_for_cell(&self, location: &Point2D, cell: &Cell, marks: DrawMarks) -> Pixel {
+        match cell.state {
+            CellState::Closed if marks.mine_hint && cell.cell_type == CellType::Mine => pxl_fg('?', Color::DarkRed),
+            CellState::Flagged if marks.wrong_flags && cell.cell_type == CellType::Water => pxl_fbg('X', Color::White, Color::DarkRed),
+            CellState::Closed if marks.peek => match cell.cell_type {
+                CellType::Mine => pxl_fg('*', Color::DarkGrey),
+                CellType::Water => pxl_fg((self.count_neighbours(location) + 0x30) as char, Color::DarkGrey),
+            },
+            CellState::Closed => pxl(self.glyphs.closed),
+            CellState::Flagged => pxl_fbg(self.glyphs.flag, Color::White, Color::DarkGreen),
+            CellState::Opened => self.pixel_for_open_cell(location, cell)
+        }
+    }
+
+    fn pixel_for_open_cell(&self, location: &Point2D, cell: &Cell) -> Pixel {
+        match cell.cell_type {
+            CellType::Mine => pxl_fbg(self.glyphs.mine, Color::White, Color::DarkRed),
+            CellType::Water => match self.count_neighbours(location) {
+                0 => pxl_fg(self.glyphs.empty, self.glyphs.empty_color),
+                num => pxl_fg((num + 0x30) as char, color_for_number(num)),
+            }
+        }
+    }
+
+    fn reveal_all(&mut self) {
+        self.data.size.iter_points()
+            .for_each(|location| {
+                self.open(&location);
+            });
+    }
+
+    /// Opens every water cell, flagged ones included, and leaves the mines alone.
+    pub fn open_all_safe(&mut self) {
+        self.data.size.iter_points()
+            .for_each(|location| {
+                if let Some(cell) = self.get_mut(&location) {
+                    if cell.cell_type == CellType::Water && !cell.is_open() {
+                        cell.state = CellState::Opened;
+                        self.dirty.push(location);
+                    }
+                }
+            });
+        self.safe_remaining = 0;
+    }
+
+    pub fn is_mine(&self, location: &Point2D) -> bool {
+        self.get(location).is_some_and(|cell| cell.cell_type == CellType::Mine)
+    }
+
+    /// Turns water at `location` into a mine and back, for hand-made boards.
+    pub fn toggle_mine(&mut self, location: &Point2D) {
+        let cell_type = if self.is_mine(location) { CellType::Water } else { CellType::Mine };
+        self.set_type(location, cell_type);
+    }
+
+    fn set_type(&mut self, location: &Point2D, cell_type: CellType) {
+        if let Some(cell) = self.get_mut(location) {
+            let was_safe = cell.is_closed_water();
+            cell.cell_type = cell_type;
+            let is_safe = cell.is_closed_water();
+            self.safe_remaining = self.safe_remaining + is_safe as usize - was_safe as usize;
+            self.dirty.push(location.clone());
+        }
+    }
+
+    /// Copy of the board turned a quarter clockwise.
+    pub fn rotate_cw(&self) -> Minefield {
+        self.remapped(Vec2D::rotate_cw, Vec2D::rotate_cw)
+    }
+
+    /// Copy of the board turned a quarter counterclockwise.
+    pub fn rotate_ccw(&self) -> Minefield {
+        self.remapped(Vec2D::rotate_ccw, Vec2D::rotate_ccw)
+    }
+
+    /// Copy of the board mirrored left to right.
+    pub fn flip_horizontal(&self) -> Minefield {
+        self.remapped(Vec2D::flip_horizontal, Vec2D::flip_horizontal)
+    }
+
+    /// Copy of the board mirrored top to bottom.
+    pub fn flip_vertical(&self) -> Minefield {
+        self.remapped(Vec2D::flip_vertical, Vec2D::flip_vertical)
+    }
+
+    /// Grows or shrinks the board, keeping the cells and mines that stay in range.
+    /// Added cells are closed, playable water.
+    pub fn resize(&mut self, new_size: &Size2D) {
+        self.data.resize(new_size, Cell::default());
+        if let Some(mask) = &mut self.mask {
+            mask.resize(new_size, true);
+        }
+        self.dirty.clear();
+        self.recount_safe_remaining();
+    }
+
+    /// Copy of the board with its cells and mask moved by the same coordinate remapping.
+    fn remapped(&self, cells: fn(&Vec2D<Cell>) -> Vec2D<Cell>, mask: fn(&Vec2D<bool>) -> Vec2D<bool>) -> Minefield {
+        Minefield {
+            data: cells(&self.data),
+            mask: self.mask.as_ref().map(mask),
+            dirty: Vec::new(),
+            ..self.clone()
+        }
+    }
+
+    /// Copy of the board with every mine uncovered, showing its layout.
+    fn layout(&self) -> Minefield {
+        let mut layout = self.clone();
+        layout.reveal_mines();
+        layout
+    }
+
+    /// Draws the board with every mine uncovered.
+    pub fn draw_layout(&self) -> Screen {
+        self.layout().draw()
+    }
+
+    /// The mine layout in the `Display` format: `*` for mines and `.` for water.
+    pub fn export(&self) -> String {
+        self.layout().to_string()
+    }
+
+    /// A closed board with mines wherever `mines` is true, failing with `EmptySize` if a side is zero.
+    pub fn from_mines(mines: Vec2D<bool>) -> Result<Minefield, GenerationError> {
+        validate_size(&mines.size)?;
+        Ok(Self::with_data(Vec2D::from_fn(&mines.size, |location| Cell {
+            cell_type: if mines[location] { CellType::Mine } else { CellType::Water },
+            ..Cell::default()
+        })))
+    }
+
+    /// Short code of the mine layout, `WxH:` followed by a bit per cell in row order,
+    /// six to a character. Cell states and the mask are not included.
+    pub fn layout_code(&self) -> String {
+        let bits = (0..self.size().1)
+            .flat_map(|y| (0..self.size().0).map(move |x| Point2D(x, y)))
+            .map(|location| self.is_mine(&location))
+            .collect::<Vec<_>>();
+        let digits = bits.chunks(6)
+            .map(|chunk| {
+                let value = chunk.iter().enumerate().fold(0, |value, (bit, mine)| value | ((*mine as usize) << bit));
+                LAYOUT_ALPHABET[value] as char
+            })
+            .collect::<String>();
+        format!("{}x{}:{}", self.size().0, self.size().1, digits)
+    }
+
+    /// Reads a closed board from a `layout_code`.
+    pub fn from_layout_code(code: &str) -> Option<Minefield> {
+        let (size, digits) = code.trim().split_once(':')?;
+        let (width, height) = size.split_once('x')?;
+        let size = Size2D(width.parse().ok()?, height.parse().ok()?);
+        let cell_count = size.0.checked_mul(size.1)?;
+        if cell_count == 0 || digits.len() != cell_count.div_ceil(6) {
+            return None;
+        }
+        let bits = digits.bytes()
+            .map(|digit| LAYOUT_ALPHABET.iter().position(|c| *c == digit))
+            .collect::<Option<Vec<_>>>()?
+            .into_iter()
+            .flat_map(|value| (0..6).map(move |bit| value & (1 << bit) != 0))
+            .collect::<Vec<_>>();
+        let data = Vec2D::from_fn(&size, |location| Cell {
+            cell_type: if bits[location.1 * size.0 + location.0] { CellType::Mine } else { CellType::Water },
+            ..Cell::default()
+        });
+        Some(Self::with_data(data))
+    }
+
+    /// Reads a closed board from the `export` format, one line per row, where a space
+    /// marks a cell outside a masked board.
+    pub fn from_pattern(pattern: &str) -> Result<Minefield, PatternError> {
+        let rows = pattern.lines()
+            .map(|line| line.chars().collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        let width = rows.first().map_or(0, Vec::len);
+        if width == 0 {
+            return Err(PatternError::Empty);
+        }
+        if let Some(row) = rows.iter().position(|row| row.len() != width) {
+            return Err(PatternError::RaggedRow(row));
+        }
+        let size = Size2D(width, rows.len());
+        let glyph = |location: &Point2D| rows[location.1][location.0];
+        if let Some(location) = size.iter_points().find(|location| !matches!(glyph(location), '*' | '.' | ' ')) {
+            return Err(PatternError::UnknownGlyph(glyph(&location), location));
+        }
+        let data = Vec2D::from_fn(&size, |location| Cell {
+            cell_type: if glyph(location) == '*' { CellType::Mine } else { CellType::Water },
+            ..Cell::default()
+        });
+        if size.iter_points().any(|location| glyph(&location) == ' ') {
+            Ok(Self::with_mask(data, Vec2D::from_fn(&size, |location| glyph(location) != ' ')))
+        } else {
+            Ok(Self::with_data(data))
+        }
+    }
+
+    /// Screen column of the cell at board column `x` in a drawn board.
+    pub fn screen_column(&self, x: usize) -> usize {
+        cell_column(x, self.spacing) + self.frame_size()
+    }
+
+    /// Screen row of the cell at board row `y` in a drawn board.
+    pub fn screen_row(&self, y: usize) -> usize {
+        y + self.frame_size()
+    }
+
+    /// Scrolls `viewport` so that the `cursor` cell stays visible in `view`,
+    /// along with the border next to it.
+    pub fn scroll_to(&self, viewport: &Point2D, cursor: &Point2D, view: &Size2D) -> Point2D {
+        let frame = self.frame_size();
+        let column = self.screen_column(cursor.0);
+        let row = self.screen_row(cursor.1);
+        let size = self.screen_size();
+        Point2D(
+            scroll_axis(viewport.0, column - 1 - frame, column + 1 + frame, size.0, view.0),
+            scroll_axis(viewport.1, row - frame, row + frame, size.1, view.1),
+        )
+    }
+
+    /// Flags every mine that is still closed, for a tidy board after a win.
+    pub fn flag_all_mines(&mut self) {
+        self.data.size.iter_points()
+            .filter(|location| self.get(location).is_some_and(|cell| cell.cell_type == CellType::Mine && cell.state == CellState::Closed))
+            .collect::<Vec<_>>()
+            .iter()
+            .for_each(|location| {
+                self.flag(location);
+            });
+    }
+
+    /// Opens every mine that is still closed, leaving flags in place.
+    fn reveal_mines(&mut self) {
+        self.data.size.iter_points()
+            .for_each(|location| {
+                if let Some(cell) = self.get_mut(&location) {
+                    if cell.cell_type == CellType::Mine && cell.state == CellState::Closed {
+                        cell.open();
+                        self.dirty.push(location);
+                    }
+                }
+            });
+    }
+
+    fn restore(&mut self, states: &[(Point2D, CellState)]) {
+        states.iter().for_each(|(location, state)| {
+            if let Some(cell) = self.get_mut(location).filter(|cell| cell.state != *state) {
+                let was_safe = cell.is_closed_water();
+                cell.state = *state;
+                let is_safe = cell.is_closed_water();
+                self.safe_remaining = self.safe_remaining + is_safe as usize - was_safe as usize;
+                self.dirty.push(location.clone());
+            }
+        });
+    }
+
+    /// Scans the board for closed water, unlike `remaining_safe_cells`.
+    fn only_mines_remaining(&self) -> bool {
+        self.count(Cell::is_closed_water) == 0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GenerationError {
+    /// The field has a zero width or height.
+    EmptySize,
+    TooManyMines,
+    /// No layout satisfying the constraints was found within the retry limit.
+    ConstraintUnsatisfied,
+    OutOfBounds(Point2D),
+    DuplicateMine(Point2D),
+    /// The block grid has a zero dimension or more blocks than cells along an axis.
+    InvalidBlocks,
+}
+
+/// How much guessing a board takes, see `Minefield::analyze`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BoardAnalysis {
+    /// Times the deductions got stuck and a cell had to be guessed.
+    pub guesses: usize,
+    /// Guesses where the board was down to an unresolvable pair.
+    pub fifty_fifties: usize,
+}
+
+impl BoardAnalysis {
+    pub fn solvable(&self) -> bool {
+        self.guesses == 0
+    }
+}
+
+/// Reasons `Minefield::from_pattern` rejects a pattern.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatternError {
+    Empty,
+    /// The row at this index differs in length from the first one.
+    RaggedRow(usize),
+    UnknownGlyph(char, Point2D),
+}
+
+fn validate_size(size: &Size2D) -> Result<(), GenerationError> {
+    if size.0 == 0 || size.1 == 0 {
+        return Err(GenerationError::EmptySize);
+    }
+    Ok(())
+}
+
+/// ASCII dump of the board for logs and tests, one line per row.
+impl fmt::Display for Minefield {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rows = (0..self.size().1)
+            .map(|y| (0..self.size().0)
+                .map(|x| {
+                    let location = Point2D(x, y);
+                    match self.get(&location) {
+                        None => String::from(" "),
+                        Some(cell) if cell.is_open() && cell.cell_type == CellType::Water => {
+                            self.count_neighbours(&location).to_string()
+                        }
+                        Some(cell) => cell.to_string(),
+                    }
+                })
+                .collect::<String>())
+            .collect::<Vec<_>>();
+        write!(f, "{}", rows.join("\n"))
+    }
+}
+
+pub struct RandomMineFieldGenerator<T> where T: Rng {
+    pub random: T
+}
+
+impl<T> RandomMineFieldGenerator<T> where T: Rng {
+    pub fn generate(&mut self, size: Size2D, mine_count: usize) -> Result<Minefield, GenerationError> {
+        Ok(Minefield::with_data(self.place_mines(&size, None, mine_count)?))
+    }
+
+    /// Spreads the mines out so that no 3x3 window holds more than `max_local` of them.
+    pub fn generate_spread(&mut self, size: Size2D, mine_count: usize, max_local: usize) -> Result<Minefield, GenerationError> {
+        Ok(Minefield::with_data(self.place_spread_mines(&size, mine_count, max_local)?))
+    }
+
+    /// Regenerates until the largest empty region covers at least `min_region` cells.
+    pub fn generate_with_empty_region(&mut self, size: Size2D, mine_count: usize, min_region: usize) -> Result<Minefield, GenerationError> {
+        self.generate_constrained(size, mine_count, None, min_region, None)
+    }
+
+    /// Regenerates while some click would open more than `max_opening` cells at once.
+    pub fn generate_with_max_opening(&mut self, size: Size2D, mine_count: usize, max_opening: usize) -> Result<Minefield, GenerationError> {
+        self.generate_constrained(size, mine_count, None, 0, Some(max_opening))
+    }
+
+    fn generate_constrained(&mut self, size: Size2D, mine_count: usize, max_local: Option<usize>, min_region: usize, max_opening: Option<usize>) -> Result<Minefield, GenerationError> {
+        for _ in 0..MAX_GENERATION_ATTEMPTS {
+            let minefield = match max_local {
+                Some(max_local) => self.generate_spread(size.clone(), mine_count, max_local)?,
+                None => self.generate(size.clone(), mine_count)?,
+            };
+            if minefield.largest_empty_region() >= min_region
+                && max_opening.is_none_or(|max_opening| minefield.largest_opening() <= max_opening) {
+                return Ok(minefield);
+            }
+        }
+        Err(GenerationError::ConstraintUnsatisfied)
+    }
+
+    /// Generates a minefield shaped by `mask`, placing mines only in playable cells.
+    pub fn generate_masked(&mut self, mask: Vec2D<bool>, mine_count: usize) -> Result<Minefield, GenerationError> {
+        let cells = self.place_mines(&mask.size, Some(&mask), mine_count)?;
+        Ok(Minefield::with_mask(cells, mask))
+    }
+
+    /// Splits the board into a `blocks` grid and gives each block a share of the mines
+    /// proportional to its cell count, placed randomly within the block.
+    pub fn generate_balanced(&mut self, size: Size2D, mine_count: usize, blocks: Size2D) -> Result<Minefield, GenerationError> {
+        validate_size(&size)?;
+        if blocks.0 == 0 || blocks.1 == 0 || blocks.0 > size.0 || blocks.1 > size.1 {
+            return Err(GenerationError::InvalidBlocks);
+        }
+        if size.0 * size.1 < mine_count {
+            return Err(GenerationError::TooManyMines);
+        }
+        let regions = block_regions(&size, &blocks);
+        let shares = fair_shares(&regions.iter().map(|(_, block)| block.0 * block.1).collect::<Vec<_>>(), mine_count);
+        let mut cells = Vec2D::sized(&size, Cell::default());
+        for ((corner, block), share) in regions.iter().zip(shares) {
+            let mut placed = 0;
+            while placed < share {
+                let location = Point2D(corner.0 + self.random.gen_range(0..block.0), corner.1 + self.random.gen_range(0..block.1));
+                let cell = &mut cells[&location];
+                if cell.cell_type == CellType::Water {
+                    cell.cell_type = CellType::Mine;
+                    placed += 1;
+                }
+            }
+        }
+        Ok(Minefield::with_data(cells))
+    }
+
+    /// Places the `fixed` mines first and the rest of `mine_count` randomly around them. There must be
+    /// fewer fixed mines than `mine_count`, and no location may be fixed twice.
+    pub fn generate_with_fixed(&mut self, size: Size2D, mine_count: usize, fixed: &[Point2D]) -> Result<Minefield, GenerationError> {
+        if fixed.len() >= mine_count || size.0 * size.1 < mine_count {
+            return Err(GenerationError::TooManyMines);
+        }
+        let mut minefield = generate_from_positions(size, fixed)?;
+        self.fill_mines(&mut minefield.data, None, mine_count - fixed.len());
+        minefield.recount_safe_remaining();
+        Ok(minefield)
+    }
+
+    fn place_mines(&mut self, size: &Size2D, mask: Option<&Vec2D<bool>>, mine_count: usize) -> Result<Vec2D<Cell>, GenerationError> {
+        validate_size(size)?;
+        let mut cells = Vec2D::sized(size, Cell::default());
+        let playable_count = cells.size.iter_points()
+            .filter(|location| is_unmasked(mask, location))
+            .count();
+        if playable_count < mine_count {
+            return Err(GenerationError::TooManyMines);
+        }
+        self.fill_mines(&mut cells, mask, mine_count);
+        Ok(cells)
+    }
+
+    /// Turns `mine_count` more random water cells into mines, which must fit.
+    fn fill_mines(&mut self, cells: &mut Vec2D<Cell>, mask: Option<&Vec2D<bool>>, mine_count: usize) {
+        let size = cells.size.clone();
+        let mut mines_placed = 0;
+        while mines_placed < mine_count {
+            let new_location = Point2D(self.random.gen_range(0..size.0), self.random.gen_range(0..size.1));
+            if !is_unmasked(mask, &new_location) {
+                continue;
+            }
+            let cell = cells.get_mut(&new_location).unwrap();
+            if cell.cell_type == CellType::Water {
+                cell.cell_type = CellType::Mine;
+                mines_placed += 1;
+            }
+        }
+    }
+
+    /// Places each mine on a random cell that keeps every 3x3 window around it at
+    /// `max_local` mines or fewer, failing once no such cell is left.
+    fn place_spread_mines(&mut self, size: &Size2D, mine_count: usize, max_local: usize) -> Result<Vec2D<Cell>, GenerationError> {
+        validate_size(size)?;
+        let mut cells = Vec2D::sized(size, Cell::default());
+        if size.0 * size.1 < mine_count {
+            return Err(GenerationError::TooManyMines);
+        }
+        for _ in 0..mine_count {
+            let candidates = size.iter_points()
+                .filter(|location| cells.get(location).is_some_and(|cell| cell.cell_type == CellType::Water))
+                .filter(|location| {
+                    let mut centres = location.neighbours();
+                    centres.push(location.clone());
+                    centres.iter().all(|centre| window_mines(&cells, centre) < max_local)
+                })
+                .collect::<Vec<_>>();
+            if candidates.is_empty() {
+                return Err(GenerationError::ConstraintUnsatisfied);
+            }
+            let location = &candidates[self.random.gen_range(0..candidates.len())];
+            cells.get_mut(location).unwrap().cell_type = CellType::Mine;
+        }
+        Ok(cells)
+    }
+}
+
+/// Top left corner and size of each block when splitting `size` into a `blocks` grid,
+/// with the sizes differing by at most one cell along each axis.
+fn block_regions(size: &Size2D, blocks: &Size2D) -> Vec<(Point2D, Size2D)> {
+    let bounds = |length: usize, count: usize, index: usize| (index * length / count, (index + 1) * length / count);
+    blocks.iter_points()
+        .map(|block| {
+            let (left, right) = bounds(size.0, blocks.0, block.0);
+            let (top, bottom) = bounds(size.1, blocks.1, block.1);
+            (Point2D(left, top), Size2D(right - left, bottom - top))
+        })
+        .collect()
+}
+
+/// Splits `total` in proportion to `weights`, handing the rounding leftovers to the
+/// largest remainders so that the shares add up to `total`.
+fn fair_shares(weights: &[usize], total: usize) -> Vec<usize> {
+    let weight_sum = weights.iter().sum::<usize>().max(1);
+    let mut shares = weights.iter().map(|weight| total * weight / weight_sum).collect::<Vec<_>>();
+    let mut by_remainder = (0..weights.len()).collect::<Vec<_>>();
+    by_remainder.sort_by_key(|index| Reverse(total * weights[*index] % weight_sum));
+    let leftover = total - shares.iter().sum::<usize>();
+    by_remainder.iter().take(leftover).for_each(|index| shares[*index] += 1);
+    shares
+}
+
+/// Mines in the 3x3 window centred on `centre`.
+fn window_mines(cells: &Vec2D<Cell>, centre: &Point2D) -> usize {
+    let mut window = centre.neighbours();
+    window.push(centre.clone());
+    window.iter()
+        .filter(|location| cells.get(location).is_some_and(|cell| cell.cell_type == CellType::Mine))
+        .count()
 }
 
-#[cfg(test)]
-mod tests {
+/// Builds a minefield with mines at exactly the given locations, without any randomness.
+pub fn generate_from_positions(size: Size2D, mines: &[Point2D]) -> Result<Minefield, GenerationError> {
+    validate_size(&size)?;
+    let mut cells = Vec2D::sized(&size, Cell::default());
+    for location in mines {
+        let cell = cells.get_mut(location)
+            .ok_or_else(|| GenerationError::OutOfBounds(location.clone()))?;
+        if cell.cell_type == CellType::Mine {
+            return Err(GenerationError::DuplicateMine(location.clone()));
+        }
+        cell.cell_type = CellType::Mine;
+    }
+    Ok(Minefield::with_data(cells))
+}
+
+/// Test helper to set up a minefield scenario without touching the underlying grid.
+#[cfg(test)]
+pub struct MinefieldBuilder {
+    size: Size2D,
+    mines: Vec<Point2D>,
+    opened: Vec<Point2D>,
+    flagged: Vec<Point2D>,
+}
+
+#[cfg(test)]
+impl MinefieldBuilder {
+    pub fn new(size: Size2D) -> Self {
+        Self {
+            size,
+            mines: vec![],
+            opened: vec![],
+            flagged: vec![],
+        }
+    }
+
+    pub fn mine_at(mut self, location: Point2D) -> Self {
+        self.mines.push(location);
+        self
+    }
+
+    /// Marks the cell as opened without flood-filling its neighbours.
+    pub fn open(mut self, location: Point2D) -> Self {
+        self.opened.push(location);
+        self
+    }
+
+    pub fn flag(mut self, location: Point2D) -> Self {
+        self.flagged.push(location);
+        self
+    }
+
+    pub fn build(self) -> Minefield {
+        let mut minefield = Minefield::new_empty(&self.size).unwrap();
+        self.mines.iter().for_each(|location| minefield.get_mut(location).unwrap().cell_type = CellType::Mine);
+        self.flagged.iter().for_each(|location| minefield.get_mut(location).unwrap().state = CellState::Flagged);
+        self.opened.iter().for_each(|location| minefield.get_mut(location).unwrap().state = CellState::Opened);
+        minefield.recount_safe_remaining();
+        minefield
+    }
+}
+
+fn is_unmasked(mask: Option<&Vec2D<bool>>, location: &Point2D) -> bool {
+    mask.is_none_or(|mask| mask.get(location).copied().unwrap_or(false))
+}
+
+fn color_for_probability(probability: f64) -> Color {
+    let idx = (probability.clamp(0.0, 1.0) * (HEAT_COLORS.len() - 1) as f64).round() as usize;
+    HEAT_COLORS[idx]
+}
+
+fn color_for_number(number: u8) -> Color {
+    NUMBER_COLORS.get(number as usize).cloned().unwrap_or(Color::White)
+}
+
+/// Everything needed to set up a new random game.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameSettings {
+    pub size: Size2D,
+    pub mine_count: usize,
+    pub lives: u8,
+    /// Minimum size of the largest empty region, 0 to allow any layout.
+    pub min_empty_region: usize,
+    pub loss_reveal: LossReveal,
+    /// Moves the mine away when the player opens one side of an unresolvable 50/50.
+    pub fair: bool,
+    /// Whether opening a cell without adjacent mines also opens its neighbours.
+    pub flood: bool,
+    /// Whether flood fills leave flagged cells closed, see `Minefield::open`.
+    pub flood_respects_flags: bool,
+    /// Unscored game that allows showing the mines while playing.
+    pub practice: bool,
+    pub topology: Topology,
+    /// Most mines allowed in any 3x3 window, `None` for purely random placement.
+    pub max_local_mines: Option<usize>,
+    /// Safe cells opened as a head start before the first move.
+    pub opening_reveals: usize,
+    /// Whether the arrow keys skip over opened cells to the next closed one.
+    pub skip_opened: bool,
+    /// Whether restarting leaves the cursor where it was instead of the top left corner.
+    pub keep_cursor: bool,
+    /// Time without progress after which a random mine is given away, `None` to never do so.
+    pub penalty_interval: Option<Duration>,
+    /// Whether flags are ignored until a cell has been opened.
+    pub open_first: bool,
+    pub glyphs: Glyphs,
+    /// Largest share of the board a single click may open, `None` to allow clearing it at once.
+    pub max_opening: Option<f64>,
+}
+
+impl Default for GameSettings {
+    fn default() -> Self {
+        Self {
+            size: Size2D(16, 10),
+            mine_count: 20,
+            lives: 1,
+            min_empty_region: 0,
+            loss_reveal: LossReveal::default(),
+            fair: false,
+            flood: true,
+            flood_respects_flags: true,
+            practice: false,
+            topology: Topology::default(),
+            max_local_mines: None,
+            opening_reveals: 0,
+            skip_opened: false,
+            keep_cursor: false,
+            penalty_interval: None,
+            open_first: false,
+            glyphs: Glyphs::default(),
+            max_opening: None,
+        }
+    }
+}
+
+/// Summary of a finished game, assembled before the board is revealed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameStats {
+    pub duration: Duration,
+    pub cells_opened: usize,
+    pub flags_used: usize,
+    /// Opens, flags and chords that changed the field.
+    pub actions: usize,
+    pub won: bool,
+}
+
+impl GameStats {
+    fn from_minefield(field: &Minefield, duration: Duration, actions: usize, won: bool) -> Self {
+        Self {
+            duration,
+            cells_opened: field.count_in_state(CellState::Opened),
+            flags_used: field.count_in_state(CellState::Flagged),
+            actions,
+            won,
+        }
+    }
+}
+
+/// Turns a held key into repeated presses after an initial delay.
+#[derive(Debug, Clone, Default)]
+pub struct KeyRepeat {
+    held_frames: usize,
+}
+
+impl KeyRepeat {
+    fn tick(&mut self, held: bool) -> bool {
+        if !held {
+            self.held_frames = 0;
+            return false;
+        }
+        let frame = self.held_frames;
+        self.held_frames += 1;
+        frame == 0 || (frame >= REPEAT_DELAY_FRAMES && (frame - REPEAT_DELAY_FRAMES).is_multiple_of(REPEAT_INTERVAL_FRAMES))
+    }
+}
+
+/// Seed for a new board taken from the system clock.
+pub fn clock_seed() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH)
+        .map(|time| time.as_nanos() as u64)
+        .unwrap_or_default()
+}
+
+/// Moves `cursor` with the arrow keys, using one repeat timer per direction
+/// (left, up, right, down), and keeps it within `size`.
+pub fn move_cursor(cursor: &mut Point2D, repeat: &mut [KeyRepeat; 4], input: &dyn Input, size: &Size2D) {
+    let [left, up, right, down] = repeat;
+    if left.tick(input.is_key_held(KeyCode::Left)) && cursor.0 > 0 {
+        cursor.0 -= 1;
+    }
+    if up.tick(input.is_key_held(KeyCode::Up)) && cursor.1 > 0 {
+        cursor.1 -= 1;
+    }
+    if right.tick(input.is_key_held(KeyCode::Right)) {
+        cursor.0 += 1;
+    }
+    if down.tick(input.is_key_held(KeyCode::Down)) {
+        cursor.1 += 1;
+    }
+    cursor.clip_excl(size);
+}
+
+/// Prior states of all cells changed by a single player action.
+type UndoEntry = Vec<(Point2D, CellState)>;
+
+/// Cursor, viewport and quit prompt, which change the screen without a key binding being pressed.
+type FrameSnapshot = (Point2D, Point2D, bool);
+
+pub struct Game {
+    settings: GameSettings,
+    field: Minefield,
+    cursor: Point2D,
+    game_over: bool,
+    won: bool,
+    /// Set when the solver finished the board, so the result is not a genuine win.
+    assisted: bool,
+    history: VecDeque<UndoEntry>,
+    /// Repeat timers for the left, up, right and down keys.
+    cursor_repeat: [KeyRepeat; 4],
+    /// Top left corner of the visible part of the field, in field screen coordinates.
+    viewport: Point2D,
+    /// Time played so far, advanced by each update until the game is finished.
+    elapsed: Duration,
+    stats: Option<GameStats>,
+    bindings: KeyBindings,
+    show_help: bool,
+    show_probability: bool,
+    cursor_style: CursorStyle,
+    input_mode: InputMode,
+    moves: MoveLog,
+    lives: u8,
+    /// Opens, flags and chords that changed the field. A flood fill counts once.
+    action_count: usize,
+    /// The mine that ended the game, if any.
+    fatal_mine: Option<Point2D>,
+    /// Game time of the last action that changed the field, for the penalty timer.
+    last_progress: Duration,
+    /// Time into the explosion animation played before the board is revealed.
+    explosion_time: Option<Duration>,
+    /// Short feedback on the last action, such as why it did nothing.
+    notice: Option<&'static str>,
+    /// Set while asking whether Esc should really end an unfinished game.
+    confirm_quit: bool,
+    /// Fixed corner of the rectangle selected with Shift and the arrow keys; the cursor is the other.
+    selection_anchor: Option<Point2D>,
+    seed: Option<u64>,
+    /// Source of in-game chance such as penalties, continuing from the board seed so replays repeat it.
+    random: StdRng,
+    /// Tints closed mines for practice, without touching the board.
+    reveal_hint: bool,
+    /// Shows the numbers under closed cells for practice, see `DrawMarks::peek`.
+    peek: bool,
+    /// The drawn field, kept between frames and updated from the field's changed cells.
+    field_screen: Screen,
+    /// Forces a full redraw of `field_screen` after a change affecting every cell.
+    redraw_all: bool,
+    /// Cell states at the moment the game was lost, so the reveal mode can be switched afterwards.
+    loss_states: UndoEntry,
+    /// Time into the game at which each cell was opened by the player.
+    opened_at: Vec2D<Option<Duration>>,
+    /// Outcome of saving the share code, shown instead of the seed.
+    share_status: Option<String>,
+    /// Key help shown under the result instead of the stand-alone game's, see `set_result_keys`.
+    result_keys: Option<Vec<String>>,
+    /// Set by an update that changed anything visible, see `GameState::needs_redraw`.
+    needs_redraw: bool,
+    events: Box<dyn EventSink>,
+    /// Best scores so far, handed on to the stats screen and restarted games.
+    high_scores: HighScores,
+}
+
+impl Game {
+    #[cfg(test)]
+    pub fn with_minefield(field: Minefield) -> Self {
+        let settings = GameSettings {
+            size: field.size().clone(),
+            mine_count: field.mine_count(),
+            ..GameSettings::default()
+        };
+        Self::with_settings(field, settings)
+    }
+
+    pub fn with_settings(mut field: Minefield, settings: GameSettings) -> Self {
+        debug_assert_eq!(Ok(()), field.validate());
+        field.flood = settings.flood;
+        field.flood_respects_flags = settings.flood_respects_flags;
+        field.topology = settings.topology;
+        field.glyphs = settings.glyphs;
+        field.take_dirty();
+        let field_screen = field.draw();
+        let size = field.size().clone();
+        Self {
+            lives: settings.lives,
+            settings,
+            field,
+            cursor: Point2D::default(),
+            game_over: false,
+            won: false,
+            assisted: false,
+            history: VecDeque::new(),
+            cursor_repeat: Default::default(),
+            viewport: Point2D::default(),
+            elapsed: Duration::ZERO,
+            stats: None,
+            bindings: KeyBindings::default(),
+            show_help: false,
+            show_probability: false,
+            cursor_style: CursorStyle::default(),
+            input_mode: InputMode::default(),
+            moves: MoveLog::default(),
+            action_count: 0,
+            fatal_mine: None,
+            last_progress: Duration::ZERO,
+            explosion_time: None,
+            notice: None,
+            confirm_quit: false,
+            selection_anchor: None,
+            seed: None,
+            random: StdRng::seed_from_u64(0),
+            reveal_hint: false,
+            peek: false,
+            field_screen,
+            redraw_all: false,
+            loss_states: Vec::new(),
+            opened_at: Vec2D::sized(&size, None),
+            share_status: None,
+            result_keys: None,
+            needs_redraw: true,
+            events: Box::new(NoEvents),
+            high_scores: HighScores::default(),
+        }
+    }
+
+    pub fn random(settings: GameSettings) -> Result<Self, GenerationError> {
+        Self::seeded(settings, clock_seed())
+    }
+
+    /// Generates the board from `seed`, so the same seed and settings always give the same layout.
+    pub fn seeded(settings: GameSettings, seed: u64) -> Result<Self, GenerationError> {
+        let mut generator = RandomMineFieldGenerator {
+            random: StdRng::seed_from_u64(seed),
+        };
+        let max_opening = settings.max_opening.map(|share| (share * (settings.size.0 * settings.size.1) as f64) as usize);
+        let minefield = generator.generate_constrained(settings.size.clone(), settings.mine_count, settings.max_local_mines, settings.min_empty_region, max_opening)?;
+        let reveals = settings.opening_reveals;
+        let mut game = Self {
+            seed: Some(seed),
+            ..Self::with_settings(minefield, settings)
+        };
+        // Opened after the settings are applied, so the floods follow the chosen topology and flag rules.
+        game.field.open_random_safe(reveals, &mut generator.random);
+        game.random = generator.random;
+        Ok(game)
+    }
+
+    /// Code to regenerate this board, if it was generated from a seed. Options beyond
+    /// size and mine count are not part of the code.
+    pub fn share_code(&self) -> Option<ShareCode> {
+        self.seed.map(|seed| ShareCode {
+            seed,
+            size: self.settings.size.clone(),
+            mine_count: self.settings.mine_count,
+        })
+    }
+
+    /// Moves made so far, in the order they were made.
+    pub fn moves(&self) -> &MoveLog {
+        &self.moves
+    }
+
+    /// The moves as text, headed by the share code of the board. Hand-made boards have no
+    /// seed and are described as `custom:WxH:M` instead.
+    pub fn replay_log(&self) -> String {
+        let header = match self.share_code() {
+            Some(code) => code.to_string(),
+            None => format!("custom:{}x{}:{}", self.field.size().0, self.field.size().1, self.field.mine_count()),
+        };
+        self.moves.serialize(&header)
+    }
+
+    fn save_share_code(&mut self) {
+        let status = match self.share_code() {
+            Some(code) if copy_to_clipboard(&code.to_string()) => format!("Copied {} to the clipboard", code),
+            Some(code) => match fs::write(SHARE_PATH, code.to_string()) {
+                Ok(()) => format!("Saved {} to {}", code, SHARE_PATH),
+                Err(error) => format!("Saving code failed: {}", error),
+            },
+            None => String::from("Hand-made board, no code"),
+        };
+        self.share_status = Some(status);
+    }
+
+    /// Time into the game at which the player opened the cell at `location`, if they did.
+    pub fn opened_at(&self, location: &Point2D) -> Option<Duration> {
+        self.opened_at.get(location).copied().flatten()
+    }
+
+    /// Replaces the receiver of game events, which ignores them by default.
+    pub fn set_event_sink(&mut self, events: Box<dyn EventSink>) {
+        self.events = events;
+    }
+
+    pub fn set_high_scores(&mut self, high_scores: HighScores) {
+        self.high_scores = high_scores;
+    }
+
+    /// Removes the event sink, for handing it on to the next game.
+    pub fn take_event_sink(&mut self) -> Box<dyn EventSink> {
+        std::mem::replace(&mut self.events, Box::new(NoEvents))
+    }
+
+    /// Seed the board was generated from, if it was generated at all.
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    fn finish(&mut self, won: bool) {
+        let duration = self.elapsed;
+        self.stats = Some(GameStats::from_minefield(&self.field, duration, self.action_count, won && !self.assisted));
+        if won {
+            self.field.flag_all_mines();
+            self.field.reveal_all();
+            self.won = true;
+            self.events.on_win();
+        } else {
+            self.loss_states = self.field.cell_states();
+            self.apply_loss_reveal();
+            self.game_over = true;
+            self.redraw_all = true;
+            self.events.on_loss();
+        }
+    }
+
+    fn apply_loss_reveal(&mut self) {
+        self.field.restore(&self.loss_states);
+        // Giving up asks for the solution, so it is shown in full whatever the reveal setting.
+        match self.settings.loss_reveal {
+            _ if self.assisted => self.field.reveal_all(),
+            LossReveal::All => self.field.reveal_all(),
+            LossReveal::Mines => self.field.reveal_mines(),
+            LossReveal::Nothing => {}
+        }
+    }
+
+    fn cycle_loss_reveal(&mut self) {
+        self.settings.loss_reveal = self.settings.loss_reveal.cycle();
+        self.apply_loss_reveal();
+    }
+
+    fn advance_clock(&mut self, dt: Duration) {
+        if self.stats.is_none() {
+            self.elapsed += dt;
+        }
+    }
+
+    /// Once the penalty interval passed without progress, flags a random closed mine, or
+    /// opens it if the cursor is on it.
+    fn apply_penalty(&mut self) -> Option<CellType> {
+        let interval = self.settings.penalty_interval?;
+        if self.elapsed.saturating_sub(self.last_progress) < interval {
+            return None;
+        }
+        self.last_progress = self.elapsed;
+        let mine = self.field.random_closed_mine(&mut self.random)?;
+        self.needs_redraw = true;
+        let on_cursor = mine == self.cursor;
+        let opened_type = self.track(MoveKind::Penalty, &mine, |field| {
+            if on_cursor {
+                return field.open(&mine).opened_type();
+            }
+            field.flag(&mine);
+            None
+        });
+        if on_cursor {
+            self.events.on_open(&mine);
+        } else {
+            self.events.on_flag(&mine);
+            self.notice = Some("Penalty!");
+        }
+        opened_type
+    }
+
+    /// Consumes a life after opening a mine, starting the explosion once none are left.
+    fn lose_life(&mut self) {
+        self.lives = self.lives.saturating_sub(1);
+        if self.lives == 0 {
+            self.explosion_time = Some(Duration::ZERO);
+        }
+    }
+
+    /// Only practice games may peek at the mines.
+    fn toggle_reveal_hint(&mut self) {
+        if self.settings.practice {
+            self.reveal_hint = !self.reveal_hint;
+            self.redraw_all = true;
+        }
+    }
+
+    fn toggle_peek(&mut self) {
+        if self.settings.practice {
+            self.peek = !self.peek;
+            self.redraw_all = true;
+        }
+    }
+
+    pub fn settings(&self) -> &GameSettings {
+        &self.settings
+    }
+
+    pub fn bindings(&self) -> &KeyBindings {
+        &self.bindings
+    }
+
+    /// Replaces the key help shown once the game is over, for states running the game that handle
+    /// those keys themselves.
+    pub fn set_result_keys(&mut self, lines: Vec<String>) {
+        self.result_keys = Some(lines);
+    }
+
+    /// Whether this frame's input would restart the game, so a state running it can restart it its own way.
+    pub fn restart_pressed(&self, input: &dyn Input) -> bool {
+        !self.confirm_quit && input.is_key_pressed(self.bindings.restart)
+    }
+
+    /// Stats of the finished game, `None` while it is still running.
+    pub fn stats(&self) -> Option<&GameStats> {
+        self.stats.as_ref()
+    }
+
+    /// Plain words for the cell under the cursor, for a status line readable without the grid.
+    pub fn cursor_description(&self) -> String {
+        match self.field.get(&self.cursor) {
+            None => String::new(),
+            Some(cell) => match (cell.state, cell.cell_type) {
+                (CellState::Closed, _) => String::from("closed"),
+                (CellState::Flagged, _) => String::from("flagged"),
+                (CellState::Opened, CellType::Mine) => String::from("mine"),
+                (CellState::Opened, CellType::Water) => match self.field.count_neighbours(&self.cursor) {
+                    0 => String::from("no mines nearby"),
+                    1 => String::from("1 mine nearby"),
+                    count => format!("{} mines nearby", count),
+                },
+            },
+        }
+    }
+
+    fn stats_screen(&mut self) -> Option<SystemEvent> {
+        let stats = self.stats.clone()?;
+        let mut screen = StatsScreen::new(stats, self.settings.clone(), std::mem::take(&mut self.high_scores));
+        screen.set_event_sink(self.take_event_sink());
+        Some(SystemEvent::ChangeState(Box::new(screen)))
+    }
+
+    /// Applies `action` to the field, logs it if anything changed and records it
+    /// for undo, unless it opened a mine.
+    fn track<F>(&mut self, kind: MoveKind, location: &Point2D, action: F) -> Option<CellType> where F: FnOnce(&mut Minefield) -> Option<CellType> {
+        let before = self.field.cell_states();
+        let opened_type = action(&mut self.field);
+        let entry: UndoEntry = before.into_iter()
+            .filter(|(location, state)| self.field.get(location).is_some_and(|cell| cell.state != *state))
+            .collect();
+        if entry.is_empty() {
+            return opened_type;
+        }
+        entry.iter()
+            .filter(|(location, _)| self.field.get(location).is_some_and(Cell::is_open))
+            .for_each(|(location, _)| {
+                if let Some(opened_at) = self.opened_at.get_mut(location) {
+                    *opened_at = Some(self.elapsed);
+                }
+            });
+        self.moves.push(self.elapsed, kind, location.clone());
+        self.last_progress = self.elapsed;
+        if !matches!(kind, MoveKind::Hint | MoveKind::Penalty) {
+            self.action_count += 1;
+        }
+        if opened_type == Some(CellType::Mine) {
+            self.fatal_mine = entry.into_iter()
+                .map(|(location, _)| location)
+                .find(|location| self.field.get(location).is_some_and(|cell| cell.is_open() && cell.cell_type == CellType::Mine));
+        } else {
+            self.record_history(entry);
+        }
+        opened_type
+    }
+
+    fn open(&mut self, location: &Point2D) -> Option<CellType> {
+        if self.settings.fair {
+            self.resolve_fifty_fifty(location);
+        }
+        let mut result = OpenResult::OutOfBounds;
+        let opened_type = self.track(MoveKind::Open, location, |field| {
+            result = field.open(location);
+            result.opened_type()
+        });
+        self.notice = (result == OpenResult::Flagged).then_some("Unflag first");
+        if result.opened_type().is_some() {
+            self.events.on_open(location);
+        }
+        opened_type
+    }
+
+    /// Spares the player a coin flip by moving the mine to the other cell of the pair.
+    fn resolve_fifty_fifty(&mut self, location: &Point2D) {
+        let partner = self.field.fifty_fifties().into_iter()
+            .find_map(|(a, b)| if a == *location { Some(b) } else if b == *location { Some(a) } else { None });
+        if let Some(partner) = partner {
+            self.field.relocate_mine(location, &partner);
+        }
+    }
+
+    /// Contents of the box shown once the game is over.
+    fn result_lines(&self, stats: &GameStats) -> Vec<String> {
+        let title = match (self.assisted, self.won) {
+            (true, true) => "Solved!",
+            (true, false) => "Gave Up!",
+            (false, true) => "You Won!",
+            (false, false) => "Game Over!",
+        };
+        let mut lines = vec![
+            String::from(title),
+            format!("Time: {}s  Mines: {}", stats.duration.as_secs(), self.field.mine_count()),
+        ];
+        if self.game_over && !self.assisted {
+            lines.push(format!("Reveal: {}", self.settings.loss_reveal.name()));
+        }
+        match &self.result_keys {
+            Some(keys) => lines.extend(keys.iter().cloned()),
+            None => {
+                lines.push(format!("{}: Stats", key_name(self.bindings.stats)));
+                lines.push(format!("{}: Replay  {}: Menu  Esc: Quit", key_name(self.bindings.restart), key_name(self.bindings.menu)));
+            }
+        }
+        lines
+    }
+
+    /// Whether the cursor is on an opened number that has as many flags around it as its value.
+    fn chord_ready(&self) -> bool {
+        let on_number = self.field.get(&self.cursor).is_some_and(|cell| cell.is_open() && cell.cell_type == CellType::Water);
+        self.stats.is_none() && on_number && self.field.known_mines(&self.cursor) == self.field.count_neighbours(&self.cursor) as usize
+    }
+
+    /// Cells a chord at the cursor would open, if the number there is satisfied by its flags.
+    fn chord_targets(&self) -> Vec<Point2D> {
+        if !self.chord_ready() {
+            return Vec::new();
+        }
+        self.field.closed_unflagged_neighbours(&self.cursor)
+    }
+
+    /// Cells of the rectangle between the selection anchor and the cursor, if a selection is active.
+    fn selected_cells(&self) -> Option<Vec<Point2D>> {
+        let anchor = self.selection_anchor.as_ref()?;
+        let corner = Point2D(min(anchor.0, self.cursor.0), min(anchor.1, self.cursor.1));
+        let size = Size2D(anchor.0.abs_diff(self.cursor.0) + 1, anchor.1.abs_diff(self.cursor.1) + 1);
+        Some(size.iter_points().map(|point| point + &corner).collect())
+    }
+
+    /// Opens every closed cell of the selection, stopping at the first mine.
+    fn open_selection(&mut self, cells: &[Point2D]) -> Option<CellType> {
+        self.selection_anchor = None;
+        for location in cells {
+            if self.field.get(location).is_some_and(|cell| cell.state == CellState::Closed) {
+                if let Some(CellType::Mine) = self.open(location) {
+                    return Some(CellType::Mine);
+                }
+            }
+        }
+        None
+    }
+
+    /// Flags every closed cell of the selection, or clears the flags if none are left closed.
+    fn flag_selection(&mut self, cells: &[Point2D]) {
+        self.selection_anchor = None;
+        let in_state = |field: &Minefield, state| cells.iter()
+            .filter(|location| field.get(location).is_some_and(|cell| cell.state == state))
+            .cloned()
+            .collect::<Vec<_>>();
+        let mut targets = in_state(&self.field, CellState::Closed);
+        if targets.is_empty() {
+            targets = in_state(&self.field, CellState::Flagged);
+        }
+        targets.iter().for_each(|location| {
+            self.flag(location);
+        });
+    }
+
+    fn chord(&mut self, location: &Point2D) -> Option<CellType> {
+        self.track(MoveKind::Chord, location, |field| field.chord(location))
+    }
+
+    fn flag(&mut self, location: &Point2D) {
+        let mut result = FlagResult::NoOp;
+        self.track(MoveKind::Flag, location, |field| {
+            result = field.flag(location);
+            None
+        });
+        if result != FlagResult::NoOp {
+            self.events.on_flag(location);
+        }
+    }
+
+    fn auto_flag(&mut self, location: &Point2D) {
+        self.track(MoveKind::AutoFlag, location, |field| {
+            field.auto_flag(location);
+            None
+        });
+    }
+
+    fn solve_step(&mut self) -> Option<CellType> {
+        let location = self.cursor.clone();
+        self.track(MoveKind::Hint, &location, |field| {
+            let opened_mines = field.opened_mine_count();
+            field.solve_step();
+            (field.opened_mine_count() > opened_mines).then_some(CellType::Mine)
+        })
+    }
+
+    fn sweep(&mut self) -> Option<CellType> {
+        let location = self.cursor.clone();
+        self.track(MoveKind::Hint, &location, |field| {
+            let opened_mines = field.opened_mine_count();
+            field.sweep();
+            (field.opened_mine_count() > opened_mines).then_some(CellType::Mine)
+        })
+    }
+
+    fn give_up(&mut self) {
+        let opened_mines = self.field.opened_mine_count();
+        self.field.sweep();
+        self.assisted = true;
+        let solved = self.field.opened_mine_count() == opened_mines && self.field.only_mines_remaining();
+        self.finish(solved);
+    }
+
+    fn record_history(&mut self, entry: UndoEntry) {
+        if self.history.len() == UNDO_LIMIT {
+            self.history.pop_front();
+        }
+        self.history.push_back(entry);
+    }
+
+    fn undo(&mut self) {
+        if let Some(entry) = self.history.pop_back() {
+            self.moves.push(self.elapsed, MoveKind::Undo, self.cursor.clone());
+            self.field.restore(&entry);
+            entry.iter()
+                .filter(|(location, _)| !self.field.get(location).is_some_and(Cell::is_open))
+                .for_each(|(location, _)| {
+                    if let Some(opened_at) = self.opened_at.get_mut(location) {
+                        *opened_at = None;
+                    }
+                });
+        }
+    }
+
+    fn move_cursor(&mut self, input: &dyn Input) {
+        let shifted = |key| input.is_key_pressed_with_modifier(key, KeyModifiers::SHIFT, KeyEventKind::Press);
+        let step = [KeyCode::Left, KeyCode::Up, KeyCode::Right, KeyCode::Down].into_iter().position(shifted);
+        if let Some(step) = step {
+            self.selection_anchor.get_or_insert(self.cursor.clone());
+            let cursor = &mut self.cursor;
+            match step {
+                0 => cursor.0 = cursor.0.saturating_sub(1),
+                1 => cursor.1 = cursor.1.saturating_sub(1),
+                2 => cursor.0 += 1,
+                _ => cursor.1 += 1,
+            }
+            cursor.clip_excl(self.field.size());
+        } else {
+            let before = self.cursor.clone();
+            if self.settings.skip_opened {
+                for (index, (key, direction)) in ARROW_KEYS.into_iter().enumerate() {
+                    if self.cursor_repeat[index].tick(input.is_key_held(key)) {
+                        self.jump_cursor(direction);
+                    }
+                }
+            } else {
+                move_cursor(&mut self.cursor, &mut self.cursor_repeat, input, self.field.size());
+            }
+            if self.cursor != before {
+                self.selection_anchor = None;
+            }
+        }
+        self.scroll_to_cursor(&Size2D(input.get_width() as usize, input.get_height() as usize));
+    }
+
+    /// New random game with the same settings, taking over the event sink.
+    fn restart(&mut self) -> Result<Game, GenerationError> {
+        let mut game = Game::random(self.settings.clone())?;
+        if self.settings.keep_cursor {
+            game.cursor = self.cursor.clone();
+            game.cursor.clip_excl(game.field.size());
+        }
+        game.events = self.take_event_sink();
+        game.high_scores = std::mem::take(&mut self.high_scores);
+        Ok(game)
+    }
+
+    /// Moves the cursor one cell in `direction`, continuing over opened cells until it
+    /// reaches a closed or flagged one or the edge of the board.
+    pub fn jump_cursor(&mut self, direction: Direction) {
+        while let Some(next) = self.cursor.step(direction, self.field.size()) {
+            self.cursor = next;
+            if !self.field.get(&self.cursor).is_none_or(Cell::is_open) {
+                break;
+            }
+        }
+    }
+
+    fn draw_probability(&self, screen: &mut Screen, offset_x: i32, offset_y: i32) {
+        let probability = self.field.mine_probability();
+        probability.size.iter_points()
+            .filter(|location| self.field.get(location).is_some_and(|cell| cell.state == CellState::Closed))
+            .for_each(|location| {
+                let color = color_for_probability(*probability.get(&location).unwrap());
+                let x = self.field.screen_column(location.0) as i32 + offset_x;
+                let y = self.field.screen_row(location.1) as i32 + offset_y;
+                screen.set_pxl(x, y, pxl_fbg('?', Color::Black, color));
+            });
+    }
+
+    /// Colors every cell up to `frame` rings away from `origin`, the outermost ring brightest.
+    fn draw_explosion(&self, screen: &mut Screen, origin: &Point2D, frame: usize, offset_x: i32, offset_y: i32) {
+        self.field.size().iter_points()
+            .filter(|location| self.field.get(location).is_some())
+            .for_each(|location| {
+                let distance = origin.chebyshev_distance(&location);
+                if distance > frame {
+                    return;
+                }
+                let x = self.field.screen_column(location.0) as i32 + offset_x;
+                let y = self.field.screen_row(location.1) as i32 + offset_y;
+                if let Ok(pixel) = screen.get_pxl(x, y) {
+                    let bg = if distance == frame { Color::Red } else { Color::DarkRed };
+                    screen.set_pxl(x, y, pxl_fbg(pixel.chr, Color::White, bg));
+                }
+            });
+    }
+
+    fn scroll_to_cursor(&mut self, view: &Size2D) {
+        self.viewport = self.field.scroll_to(&self.viewport, &self.cursor, view);
+    }
+}
+
+impl Game {
+    fn handle_input(&mut self, input: &dyn Input, dt: Duration) -> Option<SystemEvent> {
+        if self.confirm_quit {
+            if input.is_key_pressed(KEY_CONFIRM_QUIT) {
+                return self.answer_quit(KEY_CONFIRM_QUIT);
+            }
+            if input.is_any_key_pressed() {
+                return self.answer_quit(KeyCode::Esc);
+            }
+            return None;
+        }
+        if input.is_key_pressed(KeyCode::Esc) {
+            if self.stats.is_some() {
+                return Some(SystemEvent::Exit);
+            }
+            self.confirm_quit = true;
+            return None;
+        }
+        self.advance_clock(dt);
+        let mut pressed = self.input_mode.apply(self.bindings.pressed(|key| input.is_key_pressed(key)));
+        if self.settings.open_first && self.field.count_in_state(CellState::Opened) == 0 {
+            pressed.retain(|action| !matches!(action, Action::Flag | Action::AutoFlag));
+        }
+        self.needs_redraw |= !pressed.is_empty();
+        if pressed.contains(&Action::Help) {
+            self.show_help = !self.show_help;
+        }
+        if pressed.contains(&Action::Restart) {
+            return self.restart().ok()
+                .map(|game| SystemEvent::ChangeState(Box::new(game)));
+        }
+        if pressed.contains(&Action::Menu) {
+            return Some(SystemEvent::ChangeState(Box::<MainMenu>::default()));
+        }
+        if let Some(time) = self.explosion_time {
+            let time = time + dt;
+            if !pressed.is_empty() || time >= EXPLOSION_FRAME_TIME * EXPLOSION_FRAMES {
+                self.explosion_time = None;
+                self.finish(false);
+            } else {
+                self.explosion_time = Some(time);
+            }
+            return None;
+        }
+        if self.game_over || self.won {
+            if pressed.contains(&Action::Stats) {
+                return self.stats_screen();
+            }
+            if self.game_over && !self.assisted && pressed.contains(&Action::Reveal) {
+                self.cycle_loss_reveal();
+            }
+            if pressed.contains(&Action::Share) {
+                self.save_share_code();
+            }
+            return None;
+        }
+        self.move_cursor(input);
+        if !pressed.is_empty() {
+            self.notice = None;
+        }
+        let mut opened_type = None;
+        let cursor = self.cursor.clone();
+        let selection = self.selected_cells();
+        if pressed.contains(&Action::Open) {
+            opened_type = match &selection {
+                Some(cells) => self.open_selection(cells),
+                None => self.open(&cursor),
+            };
+        }
+        if pressed.contains(&Action::Chord) {
+            opened_type = opened_type.or(self.chord(&cursor));
+        }
+        let flagged = pressed.contains(&Action::Flag) || pressed.contains(&Action::AutoFlag);
+        if pressed.contains(&Action::Flag) {
+            match &selection {
+                Some(cells) => self.flag_selection(cells),
+                None => self.flag(&cursor),
+            }
+        }
+        if pressed.contains(&Action::AutoFlag) {
+            self.auto_flag(&cursor);
+        }
+        if pressed.contains(&Action::Undo) {
+            self.undo();
+        }
+        if pressed.contains(&Action::Hint) {
+            opened_type = opened_type.or(self.solve_step());
+        }
+        if pressed.contains(&Action::Sweep) {
+            opened_type = opened_type.or(self.sweep());
+        }
+        if pressed.contains(&Action::GiveUp) {
+            self.give_up();
+            return None;
+        }
+        if pressed.contains(&Action::Spacing) {
+            self.field.spacing = self.field.spacing.toggle();
+        }
+        if pressed.contains(&Action::Border) {
+            self.field.border = !self.field.border;
+        }
+        if pressed.contains(&Action::CursorStyle) {
+            self.cursor_style = self.cursor_style.toggle();
+        }
+        if pressed.contains(&Action::Mode) {
+            self.input_mode = self.input_mode.toggle();
+        }
+        if pressed.contains(&Action::Probability) {
+            self.show_probability = !self.show_probability;
+        }
+        if pressed.contains(&Action::MineHint) {
+            self.toggle_reveal_hint();
+        }
+        if pressed.contains(&Action::Peek) {
+            self.toggle_peek();
+        }
+        opened_type = opened_type.or(self.apply_penalty());
+
+        if let Some(CellType::Mine) = opened_type {
+            self.lose_life();
+        }
+        if self.explosion_time.is_none() && (self.field.remaining_safe_cells() == 0 || (flagged && self.field.all_mines_correctly_flagged())) {
+            self.finish(true);
+        }
+
+        None
+    }
+
+    /// Resolves the quit prompt: `y` exits, any other key returns to the game.
+    fn answer_quit(&mut self, key: KeyCode) -> Option<SystemEvent> {
+        self.confirm_quit = false;
+        (key == KEY_CONFIRM_QUIT).then_some(SystemEvent::Exit)
+    }
+
+    /// Brings `field_screen` up to date, redrawing everything only if the layout changed.
+    /// Returns whether any cell had to be drawn.
+    fn refresh_field_screen(&mut self) -> bool {
+        let dirty = self.field.take_dirty();
+        let size = self.field.screen_size();
+        let resized = self.field_screen.get_width() != size.0 as u32 || self.field_screen.get_height() != size.1 as u32;
+        let marks = self.draw_marks();
+        if self.redraw_all || resized {
+            self.field_screen = self.field.draw_hinted(marks);
+            self.redraw_all = false;
+            true
+        } else {
+            self.field.redraw(&mut self.field_screen, &dirty, marks);
+            !dirty.is_empty()
+        }
+    }
+
+    fn frame_snapshot(&self) -> FrameSnapshot {
+        (self.cursor.clone(), self.viewport.clone(), self.confirm_quit)
+    }
+
+    /// Updates the field screen and records whether the frame differs from the one before `before` was taken.
+    fn end_frame(&mut self, before: FrameSnapshot) {
+        let field_changed = self.refresh_field_screen();
+        self.needs_redraw |= field_changed || self.explosion_time.is_some() || self.frame_snapshot() != before;
+    }
+
+    fn draw_marks(&self) -> DrawMarks {
+        DrawMarks {
+            mine_hint: self.reveal_hint,
+            wrong_flags: self.game_over,
+            peek: self.peek,
+        }
+    }
+}
+
+impl GameState for Game {
+    fn update(&mut self, input: &dyn Input, dt: Duration) -> Option<SystemEvent> {
+        let before = self.frame_snapshot();
+        self.needs_redraw = false;
+        let event = self.handle_input(input, dt);
+        self.end_frame(before);
+        event
+    }
+
+    fn needs_redraw(&self) -> bool {
+        self.needs_redraw
+    }
+
+    fn draw(&self, screen: &mut Screen) {
+        let field_screen = &self.field_screen;
+        let field_offset_x = field_offset(field_screen.get_width(), screen.get_width(), self.viewport.0);
+        let field_offset_y = field_offset(field_screen.get_height(), screen.get_height(), self.viewport.1);
+        screen.print_screen(field_offset_x, field_offset_y, field_screen);
+        if self.show_probability && self.stats.is_none() {
+            self.draw_probability(screen, field_offset_x, field_offset_y);
+        }
+        if let (Some(time), Some(origin)) = (self.explosion_time, &self.fatal_mine) {
+            let frame = (time.as_millis() / EXPLOSION_FRAME_TIME.as_millis()) as usize;
+            self.draw_explosion(screen, origin, frame, field_offset_x, field_offset_y);
+        }
+        self.selected_cells().unwrap_or_default().iter().for_each(|location| {
+            let x = self.field.screen_column(location.0) as i32 + field_offset_x;
+            let y = self.field.screen_row(location.1) as i32 + field_offset_y;
+            if let Ok(pixel) = screen.get_pxl(x, y) {
+                screen.set_pxl(x, y, reverse_pixel(pixel));
+            }
+        });
+        self.chord_targets().iter().for_each(|location| {
+            let x = self.field.screen_column(location.0) as i32 + field_offset_x;
+            let y = self.field.screen_row(location.1) as i32 + field_offset_y;
+            if let Ok(pixel) = screen.get_pxl(x, y) {
+                screen.set_pxl(x, y, pxl_fbg(pixel.chr, Color::White, Color::DarkBlue));
+            }
+        });
+        let cursor_x = self.field.screen_column(self.cursor.0) as i32 + field_offset_x;
+        let cursor_y = self.field.screen_row(self.cursor.1) as i32 + field_offset_y;
+        if self.chord_ready() {
+            if let Ok(pixel) = screen.get_pxl(cursor_x, cursor_y) {
+                screen.set_pxl(cursor_x, cursor_y, pxl_fbg(pixel.chr, Color::Yellow, Color::DarkBlue));
+            }
+        }
+        match self.cursor_style {
+            CursorStyle::Brackets => {
+                screen.set_pxl(cursor_x - 1, cursor_y, pxl('['));
+                screen.set_pxl(cursor_x + 1, cursor_y, pxl(']'));
+            }
+            CursorStyle::Reverse => {
+                if let Ok(pixel) = screen.get_pxl(cursor_x, cursor_y) {
+                    screen.set_pxl(cursor_x, cursor_y, reverse_pixel(pixel));
+                }
+            }
+        }
+
+        let message_offset_y = min(field_offset_y + field_screen.get_height() as i32 + 3, screen.get_height() as i32 - 3);
+        if let Some(stats) = &self.stats {
+            let lines = self.result_lines(stats);
+            draw_message_box(screen, &lines.iter().map(String::as_str).collect::<Vec<_>>());
+        } else if self.confirm_quit {
+            screen.print(get_message_offset_x(screen, "Quit? y/n"), message_offset_y, "Quit? y/n");
+        } else if let Some(notice) = self.notice {
+            screen.print(get_message_offset_x(screen, notice), message_offset_y, notice);
+        }
+        if self.settings.lives > 1 {
+            screen.print(0, 0, &format!("Lives: {}", self.lives));
+        }
+        if self.stats.is_none() {
+            let mode = format!("Mode: {}", self.input_mode.name());
+            // Bottom row, as campaigns show the level at the top right.
+            screen.print(screen.get_width() as i32 - mode.len() as i32, screen.get_height() as i32 - 1, &mode);
+        }
+        if let (Some(status), Some(_)) = (&self.share_status, &self.stats) {
+            screen.print(0, screen.get_height() as i32 - 1, status);
+        } else if let (Some(seed), Some(_)) = (self.seed, &self.stats) {
+            screen.print(0, screen.get_height() as i32 - 1, &format!("Seed: {}", seed));
+        } else if self.stats.is_none() {
+            screen.print(0, screen.get_height() as i32 - 1, &self.cursor_description());
+        }
+        if self.show_help {
+            draw_help(screen, &self.bindings);
+        }
+    }
+}
+
+fn draw_help(screen: &mut Screen, bindings: &KeyBindings) {
+    let lines = bindings.help_lines();
+    let width = lines.iter().map(|line| line.len()).max().unwrap_or(0);
+    let padded = lines.iter().map(|line| format!("{:<width$}", line)).collect::<Vec<_>>();
+    draw_message_box(screen, &padded.iter().map(String::as_str).collect::<Vec<_>>());
+}
+
+/// Width and height of the box drawn by `draw_message_box`, border included.
+fn message_box_size(lines: &[&str]) -> Size2D {
+    let width = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0);
+    Size2D(width + 4, lines.len() + 2)
+}
+
+/// Draws `lines` centered on the screen in a framed box, each line centered in the box.
+pub fn draw_message_box(screen: &mut Screen, lines: &[&str]) {
+    let size = message_box_size(lines);
+    let (width, height) = (size.0 as i32, size.1 as i32);
+    let offset_x = screen.get_width() as i32 / 2 - width / 2;
+    let offset_y = screen.get_height() as i32 / 2 - height / 2;
+    screen.fill_rect(offset_x, offset_y, offset_x + width - 1, offset_y + height - 1, pxl(' '));
+    screen.rect_border(offset_x, offset_y, offset_x + width - 1, offset_y + height - 1, BorderStyle::new_light());
+    lines.iter().enumerate().for_each(|(idx, line)| {
+        let line_x = offset_x + (width - line.chars().count() as i32) / 2;
+        screen.print(line_x, offset_y + 1 + idx as i32, line);
+    });
+}
+
+fn get_message_offset_x(screen: &Screen, msg: &str) -> i32 {
+    (screen.get_width() / 2 - msg.len() as u32 / 2) as i32
+}
+
+#[cfg(test)]
+mod tests {
+
+    mod cell_state {
+        use crate::game::CellState;
+
+        #[test]
+        fn toggle_flag() {
+            assert_eq!(CellState::Opened, CellState::Opened.toggle_flag());
+            assert_eq!(CellState::Flagged, CellState::Closed.toggle_flag());
+            assert_eq!(CellState::Closed, CellState::Flagged.toggle_flag());
+        }
+
+        #[test]
+        fn open() {
+            assert_eq!(CellState::Opened, CellState::Opened.open());
+            assert_eq!(CellState::Opened, CellState::Closed.open());
+            assert_eq!(CellState::Flagged, CellState::Flagged.open());
+        }
+    }
+
+    mod viewport {
+        use crate::collections::Vec2D;
+        use crate::game::{scroll_axis, Cell, Game, Minefield};
+        use crate::geom::{Point2D, Size2D};
+
+        #[test]
+        fn no_scroll_when_field_fits() {
+            assert_eq!(0, scroll_axis(0, 15, 15, 20, 25));
+        }
+
+        #[test]
+        fn scroll_follows_cursor() {
+            assert_eq!(0, scroll_axis(0, 5, 5, 50, 10));
+            assert_eq!(3, scroll_axis(0, 10, 12, 50, 10));
+            assert_eq!(4, scroll_axis(8, 4, 6, 50, 10));
+            assert_eq!(40, scroll_axis(35, 50, 50, 50, 10));
+        }
+
+        #[test]
+        fn viewport_keeps_cursor_visible() {
+            let mut game = Game::with_minefield(Minefield::with_data(Vec2D::sized(&Size2D(30, 30), Cell::default())));
+            game.cursor = Point2D(29, 29);
+            game.scroll_to_cursor(&Size2D(20, 10));
+            assert_eq!(Point2D(41, 20), game.viewport);
+        }
+    }
+
+    mod message_box {
+        use crate::game::message_box_size;
+        use crate::geom::Size2D;
+
+        #[test]
+        fn box_fits_longest_line() {
+            assert_eq!(Size2D(16, 5), message_box_size(&["You Won!", "Enter: Stats", ""]));
+            assert_eq!(Size2D(4, 2), message_box_size(&[]));
+        }
+    }
+
+    mod input_mode {
+        use crate::bindings::Action;
+        use crate::game::InputMode;
+
+        #[test]
+        fn flag_mode_turns_open_into_flag() {
+            assert_eq!(vec![Action::Open], InputMode::Open.apply(vec![Action::Open]));
+            let mode = InputMode::Open.toggle();
+            assert_eq!(vec![Action::Flag, Action::Undo], mode.apply(vec![Action::Open, Action::Undo]));
+            assert_eq!(vec![Action::Flag], mode.apply(vec![Action::Open, Action::Flag]));
+        }
+    }
+
+    mod cursor_style {
+        use console_engine::Color;
+        use console_engine::pixel::{pxl, pxl_fbg, pxl_fg};
+        use crate::game::reverse_pixel;
+
+        #[test]
+        fn reverse_keeps_glyph() {
+            assert!(pxl_fbg('?', Color::Black, Color::White) == reverse_pixel(pxl('?')));
+        }
+
+        #[test]
+        fn reverse_uses_number_color() {
+            assert!(pxl_fbg('3', Color::Black, Color::Yellow) == reverse_pixel(pxl_fg('3', Color::Yellow)));
+        }
+    }
+
+    mod key_repeat {
+        use crate::game::{KeyRepeat, REPEAT_DELAY_FRAMES, REPEAT_INTERVAL_FRAMES};
+
+        #[test]
+        fn fires_on_press_then_after_delay() {
+            let mut repeat = KeyRepeat::default();
+            let fired = (0..REPEAT_DELAY_FRAMES + REPEAT_INTERVAL_FRAMES + 1)
+                .map(|_| repeat.tick(true))
+                .collect::<Vec<_>>();
+            assert!(fired[0]);
+            assert!(fired[1..REPEAT_DELAY_FRAMES].iter().all(|fired| !fired));
+            assert!(fired[REPEAT_DELAY_FRAMES]);
+            assert!(!fired[REPEAT_DELAY_FRAMES + 1]);
+            assert!(fired[REPEAT_DELAY_FRAMES + REPEAT_INTERVAL_FRAMES]);
+        }
+
+        #[test]
+        fn release_resets() {
+            let mut repeat = KeyRepeat::default();
+            assert!(repeat.tick(true));
+            assert!(!repeat.tick(true));
+            assert!(!repeat.tick(false));
+            assert!(repeat.tick(true));
+        }
+    }
+
+    mod minefield {
+        use console_engine::Color;
+        use console_engine::pixel::{pxl, pxl_fg};
+        use itertools::Itertools;
+        use crate::collections::Vec2D;
+        use crate::game::{fitting_board_size, Cell, CellSpacing, CellState, CellType, DrawMarks, FlagResult, Glyphs, Minefield, MinefieldBuilder, OpenResult, PatternError, Topology};
+        use crate::geom::{Point2D, Size2D};
+        use crate::render::StringRenderer;
+
+        #[test]
+        fn from_pattern_reads_mines() {
+            let minefield = Minefield::from_pattern("*..\n.*.\n...").unwrap();
+            assert_eq!(&Size2D(3, 3), minefield.size());
+            assert_eq!(2, minefield.mine_count());
+            assert!(minefield.is_mine(&Point2D(0, 0)));
+            assert!(minefield.is_mine(&Point2D(1, 1)));
+            assert_eq!(2, minefield.count_neighbours(&Point2D(1, 0)));
+            assert_eq!(1, minefield.count_neighbours(&Point2D(2, 2)));
+            assert_eq!("*..\n.*.\n...", minefield.export());
+        }
+
+        #[test]
+        fn closed_unflagged_neighbours_skip_flags_and_opened() {
+            // F . .
+            // o 1 .
+            // . . .
+            let minefield = MinefieldBuilder::new(Size2D(3, 3))
+                .mine_at(Point2D(0, 0))
+                .flag(Point2D(0, 0))
+                .open(Point2D(0, 1))
+                .open(Point2D(1, 1))
+                .build();
+            let neighbours = minefield.closed_unflagged_neighbours(&Point2D(1, 1));
+            assert_eq!(6, neighbours.len());
+            assert!(!neighbours.contains(&Point2D(0, 0)));
+            assert!(!neighbours.contains(&Point2D(0, 1)));
+            assert!(neighbours.contains(&Point2D(2, 2)));
+        }
+
+        #[test]
+        fn analyze_counts_guesses() {
+            let logical = Minefield::from_pattern("*.").unwrap();
+            assert_eq!(Some(true), logical.analyze(&Point2D(1, 0)).map(|analysis| analysis.solvable()));
+            let coin_flip = Minefield::from_pattern("*..\n...").unwrap();
+            let analysis = coin_flip.analyze(&Point2D(2, 1)).unwrap();
+            assert!(analysis.guesses >= 1);
+            assert_eq!(1, analysis.fifty_fifties);
+            assert_eq!(None, coin_flip.analyze(&Point2D(0, 0)));
+        }
+
+        #[test]
+        fn from_mines_builds_checkerboard() {
+            let minefield = Minefield::from_mines(Vec2D::from_fn(&Size2D(3, 3), |location| (location.0 + location.1) % 2 == 0)).unwrap();
+            assert_eq!(5, minefield.mine_count());
+            assert!(!minefield.is_mine(&Point2D(1, 0)));
+            assert_eq!(4, minefield.count_neighbours(&Point2D(1, 1)));
+            assert_eq!(3, minefield.count_neighbours(&Point2D(1, 0)));
+            assert_eq!(0, minefield.count_in_state(CellState::Opened));
+        }
+
+        #[test]
+        fn layout_code_round_trip() {
+            let minefield = Minefield::from_pattern("*...*\n.*...\n..*..\n.....\n*...*").unwrap();
+            let code = minefield.layout_code();
+            assert!(code.len() <= 9, "{}", code);
+            let decoded = Minefield::from_layout_code(&code).unwrap();
+            assert_eq!(minefield.export(), decoded.export());
+            assert_eq!(None, Minefield::from_layout_code("5x5:AAAA").map(|field| field.export()));
+            assert_eq!(None, Minefield::from_layout_code("5x5:AAAA!").map(|field| field.export()));
+        }
+
+        #[test]
+        fn from_pattern_rejects_ragged_rows() {
+            assert_eq!(Some(PatternError::RaggedRow(1)), Minefield::from_pattern("..\n.\n..").err());
+            assert_eq!(Some(PatternError::UnknownGlyph('x', Point2D(1, 0))), Minefield::from_pattern(".x").err());
+            assert_eq!(Some(PatternError::Empty), Minefield::from_pattern("").err());
+        }
+
+        #[test]
+        fn dense_board_rates_harder() {
+            let sparse = MinefieldBuilder::new(Size2D(6, 6))
+                .mine_at(Point2D(0, 0))
+                .mine_at(Point2D(5, 5))
+                .build();
+            let dense = (0..6).step_by(2)
+                .flat_map(|x| (0..6).step_by(2).map(move |y| Point2D(x, y)))
+                .fold(MinefieldBuilder::new(Size2D(6, 6)), MinefieldBuilder::mine_at)
+                .build();
+            assert!(dense.difficulty_rating() > sparse.difficulty_rating());
+            assert_eq!(dense.difficulty_rating(), dense.clone().difficulty_rating());
+        }
+
+        #[test]
+        fn cannot_open_flagged() {
+            let mut minefield = Minefield::with_data(Vec2D::sized(&Size2D(5, 5), Cell::default()));
+            let location = Point2D(0, 0);
+            minefield.flag(&location);
+            minefield.open(&location);
+            assert!(!minefield.get(&location).unwrap().is_open());
+        }
+
+        #[test]
+        fn flood_stops_at_flag() {
+            let mut minefield = MinefieldBuilder::new(Size2D(4, 1))
+                .flag(Point2D(2, 0))
+                .build();
+            minefield.open(&Point2D(0, 0));
+            assert_eq!("00F.", minefield.to_string());
+        }
+
+        #[test]
+        fn flood_through_flags_when_disabled() {
+            let mut minefield = MinefieldBuilder::new(Size2D(4, 1))
+                .flag(Point2D(2, 0))
+                .build();
+            minefield.flood_respects_flags = false;
+            minefield.open(&Point2D(0, 0));
+            assert_eq!(4, minefield.count_in_state(CellState::Opened));
+        }
+
+        #[test]
+        fn open_results() {
+            let mut minefield = MinefieldBuilder::new(Size2D(3, 1))
+                .mine_at(Point2D(0, 0))
+                .flag(Point2D(2, 0))
+                .build();
+            assert_eq!(OpenResult::Flagged, minefield.open(&Point2D(2, 0)));
+            assert_eq!(CellState::Flagged, minefield.get(&Point2D(2, 0)).unwrap().state);
+            assert_eq!(OpenResult::Water, minefield.open(&Point2D(1, 0)));
+            assert_eq!(OpenResult::AlreadyOpen, minefield.open(&Point2D(1, 0)));
+            assert_eq!(OpenResult::Mine, minefield.open(&Point2D(0, 0)));
+            assert_eq!(OpenResult::OutOfBounds, minefield.open(&Point2D(3, 0)));
+        }
+
+        #[test]
+        fn open_without_flood() {
+            let mut minefield = MinefieldBuilder::new(Size2D(3, 3)).build();
+            minefield.flood = false;
+            assert_eq!(OpenResult::Water, minefield.open(&Point2D(1, 1)));
+            assert_eq!("...\n.0.\n...", minefield.to_string());
+        }
+
+        #[test]
+        fn count_flagged_neighbours() {
+            // F . F
+            // . . .
+            // . F .
+            let minefield = MinefieldBuilder::new(Size2D(3, 3))
+                .flag(Point2D(0, 0))
+                .flag(Point2D(2, 0))
+                .flag(Point2D(1, 2))
+                .build();
+            assert_eq!(3, minefield.count_flagged_neighbours(&Point2D(1, 1)));
+            assert_eq!(2, minefield.count_flagged_neighbours(&Point2D(1, 0)));
+            assert_eq!(1, minefield.count_flagged_neighbours(&Point2D(0, 2)));
+        }
+
+        #[test]
+        fn torus_wraps_neighbours() {
+            let mut minefield = MinefieldBuilder::new(Size2D(4, 4))
+                .mine_at(Point2D(3, 3))
+                .mine_at(Point2D(3, 0))
+                .mine_at(Point2D(0, 3))
+                .build();
+            assert_eq!(0, minefield.count_neighbours(&Point2D(0, 0)));
+            minefield.topology = Topology::Torus;
+            assert_eq!(8, minefield.neighbours_of(&Point2D(0, 0)).len());
+            assert_eq!(3, minefield.count_neighbours(&Point2D(0, 0)));
+        }
+
+        #[test]
+        fn torus_flood_wraps() {
+            let mut minefield = MinefieldBuilder::new(Size2D(5, 1))
+                .mine_at(Point2D(2, 0))
+                .build();
+            minefield.topology = Topology::Torus;
+            minefield.open(&Point2D(0, 0));
+            assert_eq!("01.10", minefield.to_string());
+        }
+
+        #[test]
+        fn flag_marks_one_cell_dirty() {
+            let mut minefield = Minefield::new_empty(&Size2D(3, 3)).unwrap();
+            minefield.flag(&Point2D(1, 2));
+            assert_eq!(vec![Point2D(1, 2)], minefield.take_dirty());
+            assert!(minefield.take_dirty().is_empty());
+        }
+
+        #[test]
+        fn redraw_matches_full_draw() {
+            let mut minefield = MinefieldBuilder::new(Size2D(4, 3))
+                .mine_at(Point2D(3, 2))
+                .build();
+            let mut screen = minefield.draw();
+            minefield.open(&Point2D(0, 0));
+            minefield.flag(&Point2D(3, 2));
+            let dirty = minefield.take_dirty();
+            minefield.redraw(&mut screen, &dirty, DrawMarks::default());
+            let full = minefield.draw();
+            (0..screen.get_width() as i32).cartesian_product(0..screen.get_height() as i32).for_each(|(x, y)| {
+                assert!(full.get_pxl(x, y).unwrap() == screen.get_pxl(x, y).unwrap());
+            });
+        }
+
+        #[test]
+        fn wrong_flag_crossed_out_after_loss() {
+            let minefield = MinefieldBuilder::new(Size2D(2, 1))
+                .mine_at(Point2D(0, 0))
+                .flag(Point2D(0, 0))
+                .flag(Point2D(1, 0))
+                .build();
+            let marks = DrawMarks { wrong_flags: true, ..DrawMarks::default() };
+            let pixel = |location: &Point2D, marks| minefield.pixel_for_cell(location, minefield.get(location).unwrap(), marks);
+            assert_eq!('X', pixel(&Point2D(1, 0), marks).chr);
+            assert_eq!('F', pixel(&Point2D(0, 0), marks).chr);
+            assert_eq!('F', pixel(&Point2D(1, 0), DrawMarks::default()).chr);
+        }
+
+        #[test]
+        fn resize_keeps_mines_in_range() {
+            let mut minefield = Minefield::from_pattern("*.\n.*").unwrap();
+            minefield.resize(&Size2D(3, 1));
+            assert_eq!("*..", minefield.export());
+            assert_eq!(2, minefield.remaining_safe_cells());
+        }
+
+        #[test]
+        fn clone_is_independent() {
+            let minefield = Minefield::from_pattern("*..\n...").unwrap();
+            let mut clone = minefield.clone();
+            clone.flag(&Point2D(0, 0));
+            clone.open(&Point2D(2, 1));
+            assert_eq!(CellState::Flagged, clone.get(&Point2D(0, 0)).unwrap().state);
+            assert_eq!(1, clone.remaining_safe_cells());
+            assert_eq!(CellState::Closed, minefield.get(&Point2D(0, 0)).unwrap().state);
+            assert_eq!(5, minefield.remaining_safe_cells());
+        }
+
+        #[test]
+        fn flag_reports_change() {
+            let mut minefield = MinefieldBuilder::new(Size2D(2, 1))
+                .open(Point2D(1, 0))
+                .build();
+            assert_eq!(FlagResult::Added, minefield.flag(&Point2D(0, 0)));
+            assert_eq!(FlagResult::Removed, minefield.flag(&Point2D(0, 0)));
+            assert_eq!(FlagResult::NoOp, minefield.flag(&Point2D(1, 0)));
+            assert_eq!(FlagResult::NoOp, minefield.flag(&Point2D(2, 0)));
+            assert_eq!(CellState::Closed, minefield.get(&Point2D(0, 0)).unwrap().state);
+        }
+
+        #[test]
+        fn custom_glyphs_are_drawn() {
+            let mut minefield = MinefieldBuilder::new(Size2D(4, 1))
+                .mine_at(Point2D(0, 0))
+                .mine_at(Point2D(1, 0))
+                .open(Point2D(0, 0))
+                .flag(Point2D(1, 0))
+                .open(Point2D(3, 0))
+                .build();
+            minefield.glyphs = Glyphs { closed: '#', flag: '!', mine: '@', empty: '.', ..Glyphs::default() };
+            let pixel = |location: &Point2D| minefield.pixel_for_cell(location, minefield.get(location).unwrap(), DrawMarks::default()).chr;
+            assert_eq!('@', pixel(&Point2D(0, 0)));
+            assert_eq!('!', pixel(&Point2D(1, 0)));
+            assert_eq!('#', pixel(&Point2D(2, 0)));
+            assert_eq!('.', pixel(&Point2D(3, 0)));
+        }
+
+        #[test]
+        fn dotted_glyphs_mark_empty_cells() {
+            let mut minefield = MinefieldBuilder::new(Size2D(3, 1))
+                .mine_at(Point2D(0, 0))
+                .open(Point2D(1, 0))
+                .open(Point2D(2, 0))
+                .build();
+            let pixel = |minefield: &Minefield, location: &Point2D| minefield.pixel_for_cell(location, minefield.get(location).unwrap(), DrawMarks::default());
+            assert!(pxl(' ') == pixel(&minefield, &Point2D(2, 0)));
+            minefield.glyphs = Glyphs::dotted();
+            assert!(pxl_fg('·', Color::DarkGrey) == pixel(&minefield, &Point2D(2, 0)));
+            assert_eq!('1', pixel(&minefield, &Point2D(1, 0)).chr);
+        }
+
+        #[test]
+        fn rotate_keeps_mines_and_mask() {
+            // *
+            // . .
+            let mut data = Vec2D::sized(&Size2D(2, 2), Cell::default());
+            data.get_mut(&Point2D(0, 0)).unwrap().cell_type = CellType::Mine;
+            let minefield = Minefield::with_mask(data, l_shaped_mask());
+            let rotated = minefield.rotate_cw();
+            assert_eq!(".*\n. ", rotated.export());
+            assert!(rotated.get(&Point2D(1, 1)).is_none());
+            assert_eq!(minefield.export(), rotated.rotate_ccw().export());
+            assert_eq!(" *\n..", minefield.flip_horizontal().export());
+        }
+
+        #[test]
+        fn render_to_string() {
+            let mut minefield = MinefieldBuilder::new(Size2D(3, 2))
+                .mine_at(Point2D(0, 0))
+                .open(Point2D(1, 1))
+                .flag(Point2D(2, 0))
+                .build();
+            minefield.border = true;
+            let size = minefield.screen_size();
+            let mut renderer = StringRenderer::new(size.0, size.1);
+            minefield.render(&mut renderer, DrawMarks::default());
+            assert_eq!("┌───────┐\n│ ? ? F │\n│ ? 1 ? │\n└───────┘", renderer.to_string());
+        }
+
+        #[test]
+        fn fitting_board_fills_screen() {
+            let fitting = fitting_board_size(&Size2D(42, 25));
+            assert_eq!(Size2D(20, 23), fitting);
+            assert!(Minefield::new_empty(&fitting).unwrap().screen_size().0 <= 42);
+            assert!(Minefield::new_empty(&Size2D(21, 1)).unwrap().screen_size().0 > 42);
+            assert_eq!(Size2D(1, 1), fitting_board_size(&Size2D(0, 0)));
+        }
+
+        #[test]
+        fn draw_width_wide() {
+            let minefield = Minefield::with_data(Vec2D::sized(&Size2D(5, 3), Cell::default()));
+            assert_eq!(11, minefield.draw().get_width());
+        }
+
+        #[test]
+        fn draw_width_compact() {
+            let mut minefield = Minefield::with_data(Vec2D::sized(&Size2D(5, 3), Cell::default()));
+            minefield.spacing = CellSpacing::Compact;
+            assert_eq!(7, minefield.draw().get_width());
+        }
+
+        #[test]
+        fn draw_with_border() {
+            let mut minefield = Minefield::with_data(Vec2D::sized(&Size2D(5, 3), Cell::default()));
+            minefield.border = true;
+            let screen = minefield.draw();
+            assert_eq!(13, screen.get_width());
+            assert_eq!(5, screen.get_height());
+            assert!(pxl('┌') == screen.get_pxl(0, 0).unwrap());
+            assert!(pxl('┘') == screen.get_pxl(12, 4).unwrap());
+            assert!(pxl('?') == screen.get_pxl(minefield.screen_column(4) as i32, minefield.screen_row(2) as i32).unwrap());
+        }
+
+        #[test]
+        fn solve_step_flags_forced_mine() {
+            let mut data = Vec2D::sized(&Size2D(2, 1), Cell::default());
+            data.get_mut(&Point2D(0, 0)).unwrap().state = CellState::Opened;
+            data.get_mut(&Point2D(1, 0)).unwrap().cell_type = CellType::Mine;
+            let mut minefield = Minefield::with_data(data);
+            assert!(minefield.solve_step());
+            assert_eq!(CellState::Flagged, minefield.get(&Point2D(1, 0)).unwrap().state);
+            assert!(!minefield.solve_step());
+        }
+
+        #[test]
+        fn solve_step_opens_forced_safe() {
+            let mut data = Vec2D::sized(&Size2D(3, 1), Cell::default());
+            data.get_mut(&Point2D(0, 0)).unwrap().cell_type = CellType::Mine;
+            data.get_mut(&Point2D(0, 0)).unwrap().state = CellState::Flagged;
+            data.get_mut(&Point2D(1, 0)).unwrap().state = CellState::Opened;
+            let mut minefield = Minefield::with_data(data);
+            assert!(minefield.solve_step());
+            assert!(minefield.get(&Point2D(2, 0)).unwrap().is_open());
+            assert_eq!(0, minefield.opened_mine_count());
+        }
+
+        #[test]
+        fn sweep_stops_at_coin_flip() {
+            let mut minefield = Minefield::from_pattern(".**..\n..*..\n.....\n..*..").unwrap();
+            minefield.open(&Point2D(4, 3));
+            assert!(minefield.sweep());
+            let closed = minefield.data.size.iter_points()
+                .filter(|location| minefield.get(location).unwrap().state == CellState::Closed)
+                .collect::<Vec<_>>();
+            // The mine is in either of the top left cells, which no number tells apart.
+            assert_eq!(vec![Point2D(0, 0), Point2D(1, 0)], closed);
+            assert_eq!(3, minefield.count_in_state(CellState::Flagged));
+            assert_eq!(0, minefield.opened_mine_count());
+            assert!(!minefield.sweep());
+        }
+
+        fn l_shaped_mask() -> Vec2D<bool> {
+            // X .
+            // X X
+            let mut mask = Vec2D::sized(&Size2D(2, 2), true);
+            *mask.get_mut(&Point2D(1, 0)).unwrap() = false;
+            mask
+        }
+
+        #[test]
+        fn count_neighbours_skips_masked_cells() {
+            let mut data = Vec2D::sized(&Size2D(2, 2), Cell::default());
+            data.get_mut(&Point2D(1, 0)).unwrap().cell_type = CellType::Mine;
+            data.get_mut(&Point2D(0, 1)).unwrap().cell_type = CellType::Mine;
+            let minefield = Minefield::with_mask(data, l_shaped_mask());
+            assert_eq!(1, minefield.count_neighbours(&Point2D(0, 0)));
+            assert_eq!(1, minefield.count_neighbours(&Point2D(1, 1)));
+            assert!(minefield.get(&Point2D(1, 0)).is_none());
+        }
+
+        #[test]
+        fn only_mines_remaining_ignores_masked_cells() {
+            let mut minefield = Minefield::with_mask(Vec2D::sized(&Size2D(2, 2), Cell::default()), l_shaped_mask());
+            minefield.reveal_all();
+            assert!(minefield.only_mines_remaining());
+            assert_eq!(CellState::Closed, minefield.data.get(&Point2D(1, 0)).unwrap().state);
+        }
+
+        #[test]
+        fn validate_well_formed_board() {
+            let minefield = Minefield::with_mask(Vec2D::sized(&Size2D(2, 2), Cell::default()), l_shaped_mask());
+            assert_eq!(Ok(()), minefield.validate());
+        }
+
+        #[test]
+        fn validate_reports_stale_counters() {
+            let mut minefield = Minefield::from_pattern("*..").unwrap();
+            minefield.safe_remaining = 1;
+            minefield.dirty.push(Point2D(3, 0));
+            assert_eq!(Err(vec![
+                String::from("1 safe cells cached but 2 closed water cells on the board"),
+                String::from("changed cell Point2D(3, 0) is off the board"),
+            ]), minefield.validate());
+        }
+
+        #[test]
+        fn validate_reports_mine_under_mask() {
+            let mut minefield = Minefield::with_mask(Vec2D::sized(&Size2D(2, 2), Cell::default()), l_shaped_mask());
+            minefield.data.get_mut(&Point2D(1, 0)).unwrap().cell_type = CellType::Mine;
+            assert_eq!(Err(vec![String::from("masked cell Point2D(1, 0) holds a mine")]), minefield.validate());
+        }
+
+        #[test]
+        fn count_by_predicate() {
+            let minefield = MinefieldBuilder::new(Size2D(3, 2))
+                .mine_at(Point2D(0, 0))
+                .mine_at(Point2D(2, 1))
+                .flag(Point2D(0, 0))
+                .flag(Point2D(1, 1))
+                .open(Point2D(1, 0))
+                .build();
+            assert_eq!(2, minefield.count(|cell| cell.cell_type == CellType::Mine));
+            assert_eq!(2, minefield.count(|cell| cell.state == CellState::Flagged));
+            assert_eq!(1, minefield.count(|cell| cell.is_open()));
+            assert_eq!(1, minefield.count(|cell| cell.state == CellState::Flagged && cell.cell_type == CellType::Water));
+        }
+
+        #[test]
+        fn count_skips_masked_cells() {
+            let minefield = Minefield::with_mask(Vec2D::sized(&Size2D(2, 2), Cell::default()), l_shaped_mask());
+            assert_eq!(3, minefield.count(|_| true));
+        }
+
+        #[test]
+        fn all_mines_correctly_flagged() {
+            let mut data = Vec2D::sized(&Size2D(3, 1), Cell::default());
+            data.get_mut(&Point2D(0, 0)).unwrap().cell_type = CellType::Mine;
+            data.get_mut(&Point2D(2, 0)).unwrap().cell_type = CellType::Mine;
+            let mut minefield = Minefield::with_data(data);
+            minefield.flag(&Point2D(0, 0));
+            assert!(!minefield.all_mines_correctly_flagged());
+            minefield.flag(&Point2D(2, 0));
+            assert!(minefield.all_mines_correctly_flagged());
+        }
+
+        #[test]
+        fn misplaced_flags_with_equal_count() {
+            let mut data = Vec2D::sized(&Size2D(3, 1), Cell::default());
+            data.get_mut(&Point2D(0, 0)).unwrap().cell_type = CellType::Mine;
+            data.get_mut(&Point2D(2, 0)).unwrap().cell_type = CellType::Mine;
+            let mut minefield = Minefield::with_data(data);
+            minefield.flag(&Point2D(0, 0));
+            minefield.flag(&Point2D(1, 0));
+            assert!(!minefield.all_mines_correctly_flagged());
+        }
+
+        #[test]
+        fn mine_probability_certain_mine() {
+            let mut data = Vec2D::sized(&Size2D(2, 1), Cell::default());
+            data.get_mut(&Point2D(0, 0)).unwrap().state = CellState::Opened;
+            data.get_mut(&Point2D(1, 0)).unwrap().cell_type = CellType::Mine;
+            let minefield = Minefield::with_data(data);
+            let probability = minefield.mine_probability();
+            assert_eq!(&1.0, probability.get(&Point2D(1, 0)).unwrap());
+            assert_eq!(&0.0, probability.get(&Point2D(0, 0)).unwrap());
+        }
+
+        #[test]
+        fn mine_probability_certain_safe() {
+            let mut data = Vec2D::sized(&Size2D(3, 1), Cell::default());
+            data.get_mut(&Point2D(0, 0)).unwrap().cell_type = CellType::Mine;
+            data.get_mut(&Point2D(0, 0)).unwrap().state = CellState::Flagged;
+            data.get_mut(&Point2D(1, 0)).unwrap().state = CellState::Opened;
+            let minefield = Minefield::with_data(data);
+            assert_eq!(&0.0, minefield.mine_probability().get(&Point2D(2, 0)).unwrap());
+        }
+
+        #[test]
+        fn auto_flag_forced_neighbours() {
+            // M 2 M
+            // 1 2 1
+            let mut minefield = MinefieldBuilder::new(Size2D(3, 2))
+                .mine_at(Point2D(0, 0))
+                .mine_at(Point2D(2, 0))
+                .open(Point2D(1, 0))
+                .open(Point2D(0, 1))
+                .open(Point2D(1, 1))
+                .open(Point2D(2, 1))
+                .build();
+            assert_eq!(2, minefield.auto_flag(&Point2D(1, 0)));
+            assert_eq!(CellState::Flagged, minefield.get(&Point2D(0, 0)).unwrap().state);
+            assert_eq!(CellState::Flagged, minefield.get(&Point2D(2, 0)).unwrap().state);
+            assert_eq!(0, minefield.auto_flag(&Point2D(1, 0)));
+        }
+
+        #[test]
+        fn auto_flag_ignores_undetermined_number() {
+            let mut minefield = MinefieldBuilder::new(Size2D(3, 1))
+                .mine_at(Point2D(0, 0))
+                .open(Point2D(1, 0))
+                .build();
+            assert_eq!(0, minefield.auto_flag(&Point2D(1, 0)));
+        }
+
+        #[test]
+        fn display() {
+            let mut minefield = MinefieldBuilder::new(Size2D(3, 2))
+                .mine_at(Point2D(0, 0))
+                .mine_at(Point2D(2, 1))
+                .flag(Point2D(0, 0))
+                .open(Point2D(1, 0))
+                .open(Point2D(0, 1))
+                .build();
+            minefield.open(&Point2D(2, 1));
+            assert_eq!("F2.\n1.*", minefield.to_string());
+        }
+
+        #[test]
+        fn display_cell() {
+            let cell = Cell {
+                cell_type: CellType::Water,
+                state: CellState::Opened,
+            };
+            assert_eq!("o", cell.to_string());
+            assert_eq!(".", Cell::default().to_string());
+        }
+
+        #[test]
+        fn remaining_safe_cells_counts_down_to_win() {
+            let mut minefield = MinefieldBuilder::new(Size2D(3, 2))
+                .mine_at(Point2D(0, 0))
+                .mine_at(Point2D(2, 1))
+                .build();
+            assert_eq!(4, minefield.remaining_safe_cells());
+            minefield.open(&Point2D(1, 0));
+            assert_eq!(3, minefield.remaining_safe_cells());
+            [Point2D(2, 0), Point2D(0, 1), Point2D(1, 1)].iter().for_each(|location| {
+                minefield.open(location);
+            });
+            assert_eq!(0, minefield.remaining_safe_cells());
+            assert!(minefield.only_mines_remaining());
+        }
+
+        #[test]
+        fn safe_counter_matches_scan_after_changes() {
+            let mut minefield = MinefieldBuilder::new(Size2D(4, 3))
+                .mine_at(Point2D(0, 0))
+                .mine_at(Point2D(3, 2))
+                .build();
+            let before = minefield.cell_states();
+            minefield.open(&Point2D(1, 1));
+            minefield.flag(&Point2D(2, 0));
+            minefield.relocate_mine(&Point2D(0, 0), &Point2D(0, 2));
+            minefield.toggle_mine(&Point2D(3, 0));
+            assert_eq!(minefield.count(Cell::is_closed_water), minefield.remaining_safe_cells());
+            assert_eq!(Ok(()), minefield.validate());
+            minefield.restore(&before);
+            assert_eq!(minefield.count(Cell::is_closed_water), minefield.remaining_safe_cells());
+            assert_eq!(Ok(()), minefield.validate());
+        }
+
+        #[test]
+        fn open_all_safe_leaves_mines_closed() {
+            let mut minefield = MinefieldBuilder::new(Size2D(3, 3))
+                .mine_at(Point2D(0, 0))
+                .mine_at(Point2D(2, 1))
+                .flag(Point2D(1, 1))
+                .build();
+            minefield.open_all_safe();
+            assert!(minefield.only_mines_remaining());
+            assert_eq!(0, minefield.remaining_safe_cells());
+            assert_eq!(0, minefield.opened_mine_count());
+        }
+
+        #[test]
+        fn only_mines_remaining_in_water_only_field() {
+            let mut minefield = Minefield::with_data(Vec2D::sized(&Size2D(5, 5), Cell::default()));
+            minefield.reveal_all();
+            assert!(minefield.only_mines_remaining());
+        }
+
+        #[test]
+        fn mines_remaining_with_closed_water() {
+            let mut data = Vec2D::sized(&Size2D(2, 1), Cell::default());
+            data.get_mut(&Point2D(0, 0)).unwrap().state = CellState::Opened;
+            let minefield = Minefield::with_data(data);
+            assert!(!minefield.only_mines_remaining());
+        }
+
+        #[test]
+        fn mines_remaining_mixed() {
+            let minefield = MinefieldBuilder::new(Size2D(2, 1))
+                .open(Point2D(0, 0))
+                .mine_at(Point2D(1, 0))
+                .build();
+            assert!(minefield.only_mines_remaining());
+        }
+
+        #[test]
+        fn builder_center_mine() {
+            let minefield = MinefieldBuilder::new(Size2D(3, 3))
+                .mine_at(Point2D(1, 1))
+                .open(Point2D(0, 0))
+                .flag(Point2D(1, 1))
+                .build();
+            assert_eq!(1, minefield.mine_count());
+            assert_eq!(CellState::Flagged, minefield.get(&Point2D(1, 1)).unwrap().state);
+            assert_eq!(1, minefield.count_in_state(CellState::Opened));
+            minefield.data.all_locations().iter()
+                .filter(|location| **location != Point2D(1, 1))
+                .for_each(|location| assert_eq!(1, minefield.count_neighbours(location)));
+        }
+    }
+
+    mod game {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+        use std::time::Duration;
+        use console_engine::KeyCode;
+        use console_engine::screen::Screen;
+        use crate::collections::Vec2D;
+        use crate::events::EventSink;
+        use crate::game::{generate_from_positions, Cell, CellState, CellType, Game, GameSettings, LossReveal, Minefield, MinefieldBuilder};
+        use crate::geom::{Direction, Point2D, Size2D};
+        use crate::input::MockInput;
+        use crate::replay::MoveKind;
+        use crate::state::{GameState, SystemEvent};
+
+        #[test]
+        fn scripted_inputs_win_game() {
+            let mut game = Game::with_minefield(Minefield::from_pattern("..*\n...").unwrap());
+            let script = [
+                MockInput::pressing(KeyCode::Char(' ')),
+                MockInput::pressing(KeyCode::Right),
+                MockInput::default(),
+                MockInput::pressing(KeyCode::Right),
+                MockInput::pressing(KeyCode::Down),
+                MockInput::pressing(KeyCode::Char(' ')),
+            ];
+            for input in &script {
+                assert!(game.update(input, Duration::from_millis(100)).is_none());
+            }
+            assert_eq!(Point2D(2, 1), game.cursor);
+            assert!(game.won);
+            assert_eq!(Some(Duration::from_millis(600)), game.stats().map(|stats| stats.duration));
+        }
+
+        #[test]
+        fn chord_ready_once_flags_match_number() {
+            let field = MinefieldBuilder::new(Size2D(3, 1))
+                .mine_at(Point2D(0, 0))
+                .open(Point2D(1, 0))
+                .build();
+            let mut game = Game::with_minefield(field);
+            game.cursor = Point2D(1, 0);
+            assert!(!game.chord_ready());
+            game.field.flag(&Point2D(0, 0));
+            assert!(game.chord_ready());
+            game.field.flag(&Point2D(2, 0));
+            assert!(!game.chord_ready());
+        }
+
+        #[test]
+        fn penalty_flags_mine_after_idle_interval() {
+            let field = Minefield::from_pattern("*..\n...").unwrap();
+            let settings = GameSettings {
+                penalty_interval: Some(Duration::from_secs(10)),
+                ..GameSettings::default()
+            };
+            let mut game = Game::with_settings(field, settings);
+            game.cursor = Point2D(2, 1);
+            game.elapsed = Duration::from_secs(9);
+            assert_eq!(None, game.apply_penalty());
+            assert_eq!(Some(CellState::Closed), game.field.get(&Point2D(0, 0)).map(|cell| cell.state));
+            game.elapsed = Duration::from_secs(10);
+            assert_eq!(None, game.apply_penalty());
+            assert_eq!(Some(CellState::Flagged), game.field.get(&Point2D(0, 0)).map(|cell| cell.state));
+            let moves = game.moves().moves().iter()
+                .map(|m| (m.kind, m.location.clone()))
+                .collect::<Vec<_>>();
+            assert_eq!(vec![(MoveKind::Penalty, Point2D(0, 0))], moves);
+            assert_eq!(0, game.action_count);
+        }
+
+        #[test]
+        fn penalty_opens_mine_under_cursor() {
+            let events = Rc::new(RefCell::new(Vec::new()));
+            let settings = GameSettings {
+                lives: 2,
+                penalty_interval: Some(Duration::from_secs(10)),
+                ..GameSettings::default()
+            };
+            let mut game = Game::with_settings(Minefield::from_pattern("*..\n...").unwrap(), settings);
+            game.set_event_sink(Box::new(RecordingSink(events.clone())));
+            game.update(&MockInput::default(), Duration::from_secs(10));
+            assert!(game.field.get(&Point2D(0, 0)).unwrap().is_open());
+            assert_eq!(1, game.lives);
+            assert!(!game.game_over);
+            assert_eq!(vec!["open 0 0"], *events.borrow());
+        }
+
+        #[test]
+        fn seeded_penalties_repeat() {
+            let settings = GameSettings {
+                size: Size2D(9, 9),
+                mine_count: 10,
+                penalty_interval: Some(Duration::from_secs(1)),
+                ..GameSettings::default()
+            };
+            let penalties = || {
+                let mut game = Game::seeded(settings.clone(), 7).unwrap();
+                game.cursor = Point2D(9, 9);
+                (1..=5).for_each(|second| {
+                    game.elapsed = Duration::from_secs(second);
+                    game.apply_penalty();
+                });
+                game.replay_log()
+            };
+            assert_eq!(penalties(), penalties());
+        }
+
+        #[test]
+        fn open_first_ignores_early_flags() {
+            let settings = GameSettings {
+                open_first: true,
+                ..GameSettings::default()
+            };
+            let mut game = Game::with_settings(Minefield::from_pattern("*.\n..").unwrap(), settings);
+            game.cursor = Point2D(1, 1);
+            game.update(&MockInput::pressing(KeyCode::Char('f')), Duration::ZERO);
+            assert_eq!(Some(CellState::Closed), game.field.get(&Point2D(1, 1)).map(|cell| cell.state));
+            game.update(&MockInput::pressing(KeyCode::Char(' ')), Duration::ZERO);
+            game.cursor = Point2D(0, 0);
+            game.update(&MockInput::pressing(KeyCode::Char('f')), Duration::ZERO);
+            assert_eq!(Some(CellState::Flagged), game.field.get(&Point2D(0, 0)).map(|cell| cell.state));
+        }
+
+        #[test]
+        fn restart_keeps_cursor_if_enabled() {
+            let settings = GameSettings {
+                keep_cursor: true,
+                ..GameSettings::default()
+            };
+            let mut game = Game::random(settings).unwrap();
+            game.cursor = Point2D(5, 3);
+            assert_eq!(Point2D(5, 3), game.restart().unwrap().cursor);
+            game.settings.keep_cursor = false;
+            assert_eq!(Point2D(0, 0), game.restart().unwrap().cursor);
+        }
 
-    mod cell_state {
-        use crate::game::CellState;
+        #[test]
+        fn jump_cursor_stops_at_first_closed_cell() {
+            let field = MinefieldBuilder::new(Size2D(5, 1))
+                .open(Point2D(0, 0))
+                .open(Point2D(1, 0))
+                .open(Point2D(2, 0))
+                .build();
+            let mut game = Game::with_minefield(field);
+            game.jump_cursor(Direction::E);
+            assert_eq!(Point2D(3, 0), game.cursor);
+            game.jump_cursor(Direction::E);
+            assert_eq!(Point2D(4, 0), game.cursor);
+            game.jump_cursor(Direction::E);
+            assert_eq!(Point2D(4, 0), game.cursor);
+        }
 
         #[test]
-        fn toggle_flag() {
-            assert_eq!(CellState::Opened, CellState::Opened.toggle_flag());
-            assert_eq!(CellState::Flagged, CellState::Closed.toggle_flag());
-            assert_eq!(CellState::Closed, CellState::Flagged.toggle_flag());
+        fn undo_flag() {
+            let mut game = Game::with_minefield(Minefield::with_data(Vec2D::sized(&Size2D(3, 3), Cell::default())));
+            let location = Point2D(1, 1);
+            game.flag(&location);
+            assert_eq!(CellState::Flagged, game.field.get(&location).unwrap().state);
+            game.undo();
+            assert_eq!(CellState::Closed, game.field.get(&location).unwrap().state);
         }
 
         #[test]
-        fn open() {
-            assert_eq!(CellState::Opened, CellState::Opened.open());
-            assert_eq!(CellState::Opened, CellState::Closed.open());
-            assert_eq!(CellState::Flagged, CellState::Flagged.open());
+        fn undo_flood_fill() {
+            let mut game = Game::with_minefield(Minefield::with_data(Vec2D::sized(&Size2D(3, 3), Cell::default())));
+            game.open(&Point2D(0, 0));
+            game.undo();
+            assert!(game.field.cell_states().iter().all(|(_, state)| *state == CellState::Closed));
         }
-    }
 
-    mod minefield {
-        use crate::collections::Vec2D;
-        use crate::game::{Cell, CellState, CellType, Minefield};
-        use crate::geom::{Point2D, Size2D};
+        fn opened_after_loss(game: &Game) -> Vec<Point2D> {
+            game.field.cell_states().into_iter()
+                .filter(|(_, state)| *state == CellState::Opened)
+                .map(|(location, _)| location)
+                .collect()
+        }
 
         #[test]
-        fn cannot_open_flagged() {
-            let mut minefield = Minefield::with_data(Vec2D::sized(&Size2D(5, 5), Cell::default()));
-            let location = Point2D(0, 0);
-            minefield.flag(&location);
-            minefield.open(&location);
-            assert!(!minefield.get(&location).unwrap().is_open());
+        fn loss_reveal_modes() {
+            let minefield = MinefieldBuilder::new(Size2D(4, 1))
+                .mine_at(Point2D(0, 0))
+                .mine_at(Point2D(3, 0))
+                .open(Point2D(1, 0))
+                .flag(Point2D(3, 0))
+                .build();
+            let mut game = Game::with_minefield(minefield);
+            game.finish(false);
+            assert_eq!(LossReveal::All, game.settings.loss_reveal);
+            assert_eq!(vec![Point2D(0, 0), Point2D(1, 0), Point2D(2, 0)], opened_after_loss(&game));
+            game.cycle_loss_reveal();
+            assert_eq!(vec![Point2D(0, 0), Point2D(1, 0)], opened_after_loss(&game));
+            assert_eq!(CellState::Flagged, game.field.get(&Point2D(3, 0)).unwrap().state);
+            game.cycle_loss_reveal();
+            assert_eq!(vec![Point2D(1, 0)], opened_after_loss(&game));
+            game.cycle_loss_reveal();
+            assert_eq!(vec![Point2D(0, 0), Point2D(1, 0), Point2D(2, 0)], opened_after_loss(&game));
+        }
+
+        fn fifty_fifty_board(mine: Point2D) -> Minefield {
+            // ? ?
+            // 1 1
+            MinefieldBuilder::new(Size2D(2, 2))
+                .mine_at(mine)
+                .open(Point2D(0, 1))
+                .open(Point2D(1, 1))
+                .build()
         }
 
         #[test]
-        fn only_mines_remaining_in_water_only_field() {
-            let mut minefield = Minefield::with_data(Vec2D::sized(&Size2D(5, 5), Cell::default()));
-            minefield.reveal_all();
-            assert!(minefield.only_mines_remaining());
+        fn fair_mode_never_detonates_fifty_fifty() {
+            for mine in [Point2D(0, 0), Point2D(1, 0)] {
+                for guess in [Point2D(0, 0), Point2D(1, 0)] {
+                    let field = fifty_fifty_board(mine.clone());
+                    let settings = GameSettings {
+                        size: Size2D(2, 2),
+                        mine_count: 1,
+                        fair: true,
+                        ..GameSettings::default()
+                    };
+                    let mut game = Game::with_settings(field, settings);
+                    assert_eq!(Some(CellType::Water), game.open(&guess));
+                    assert_eq!(1, game.field.mine_count());
+                }
+            }
         }
 
         #[test]
-        fn mines_remaining_with_closed_water() {
+        fn unfair_mode_detonates_fifty_fifty() {
+            let mut game = Game::with_minefield(fifty_fifty_board(Point2D(0, 0)));
+            assert_eq!(Some(CellType::Mine), game.open(&Point2D(0, 0)));
+        }
+
+        #[test]
+        fn win_flags_remaining_mines() {
+            let mut game = Game::with_minefield(Minefield::from_pattern(".*\n.*").unwrap());
+            game.open(&Point2D(0, 0));
+            game.open(&Point2D(0, 1));
+            assert_eq!(0, game.field.remaining_safe_cells());
+            game.finish(true);
+            assert_eq!(Some(CellState::Flagged), game.field.get(&Point2D(1, 0)).map(|cell| cell.state));
+            assert_eq!(Some(CellState::Flagged), game.field.get(&Point2D(1, 1)).map(|cell| cell.state));
+        }
+
+        #[test]
+        fn give_up_solves_logical_board() {
+            let mut data = Vec2D::sized(&Size2D(4, 1), Cell::default());
+            data.get_mut(&Point2D(0, 0)).unwrap().state = CellState::Opened;
+            data.get_mut(&Point2D(1, 0)).unwrap().cell_type = CellType::Mine;
+            data.get_mut(&Point2D(3, 0)).unwrap().state = CellState::Opened;
+            let mut game = Game::with_minefield(Minefield::with_data(data));
+            game.give_up();
+            assert!(game.won);
+            assert!(game.assisted);
+            assert!(!game.game_over);
+        }
+
+        #[test]
+        fn give_up_terminates_on_unsolvable_board() {
+            let mut data = Vec2D::sized(&Size2D(2, 1), Cell::default());
+            data.get_mut(&Point2D(1, 0)).unwrap().cell_type = CellType::Mine;
+            let mut game = Game::with_minefield(Minefield::with_data(data));
+            game.give_up();
+            assert!(!game.won);
+            assert!(game.game_over);
+        }
+
+        #[test]
+        fn give_up_reveals_board_whatever_loss_reveal() {
+            let field = MinefieldBuilder::new(Size2D(3, 1)).mine_at(Point2D(1, 0)).build();
+            let settings = GameSettings {
+                size: Size2D(3, 1),
+                mine_count: 1,
+                loss_reveal: LossReveal::Nothing,
+                ..GameSettings::default()
+            };
+            let mut game = Game::with_settings(field, settings);
+            game.give_up();
+            assert!(game.game_over);
+            assert!((0..3).all(|x| game.field.get(&Point2D(x, 0)).is_some_and(Cell::is_open)));
+        }
+
+        #[test]
+        fn chord_opens_around_satisfied_number() {
+            let mut data = Vec2D::sized(&Size2D(3, 1), Cell::default());
+            data.get_mut(&Point2D(0, 0)).unwrap().cell_type = CellType::Mine;
+            data.get_mut(&Point2D(0, 0)).unwrap().state = CellState::Flagged;
+            data.get_mut(&Point2D(1, 0)).unwrap().state = CellState::Opened;
+            let mut game = Game::with_minefield(Minefield::with_data(data));
+            assert_eq!(Some(CellType::Water), game.chord(&Point2D(1, 0)));
+            assert!(game.field.get(&Point2D(2, 0)).unwrap().is_open());
+            game.undo();
+            assert!(!game.field.get(&Point2D(2, 0)).unwrap().is_open());
+        }
+
+        #[test]
+        fn chord_ignores_unsatisfied_number() {
+            let mut data = Vec2D::sized(&Size2D(3, 1), Cell::default());
+            data.get_mut(&Point2D(0, 0)).unwrap().cell_type = CellType::Mine;
+            data.get_mut(&Point2D(1, 0)).unwrap().state = CellState::Opened;
+            let mut game = Game::with_minefield(Minefield::with_data(data));
+            assert_eq!(None, game.chord(&Point2D(1, 0)));
+            assert!(!game.field.get(&Point2D(2, 0)).unwrap().is_open());
+        }
+
+        #[test]
+        fn moves_are_logged_in_order() {
+            let mut game = Game::with_minefield(generate_from_positions(Size2D(3, 3), &[Point2D(2, 2)]).unwrap());
+            game.open(&Point2D(0, 0));
+            game.flag(&Point2D(2, 2));
+            game.open(&Point2D(0, 0));
+            let moves = game.moves().moves().iter()
+                .map(|m| (m.kind, m.location.clone()))
+                .collect::<Vec<_>>();
+            assert_eq!(vec![(MoveKind::Open, Point2D(0, 0)), (MoveKind::Flag, Point2D(2, 2))], moves);
+            assert_eq!("custom:3x3:1\n0 open 0 0\n0 flag 2 2", game.replay_log());
+        }
+
+        #[test]
+        fn replay_log_starts_with_share_code() {
+            let settings = GameSettings {
+                size: Size2D(9, 9),
+                mine_count: 10,
+                ..GameSettings::default()
+            };
+            let mut game = Game::seeded(settings, 42).unwrap();
+            game.flag(&Point2D(4, 4));
+            assert_eq!("bombe:42:9x9:10\n0 flag 4 4", game.replay_log());
+        }
+
+        #[test]
+        fn flood_fill_counts_as_one_action() {
+            let mut game = Game::with_minefield(generate_from_positions(Size2D(4, 4), &[Point2D(3, 3)]).unwrap());
+            game.open(&Point2D(0, 0));
+            assert!(game.field.count_in_state(CellState::Opened) > 1);
+            assert_eq!(1, game.action_count);
+            game.open(&Point2D(0, 0));
+            assert_eq!(1, game.action_count);
+            game.flag(&Point2D(3, 3));
+            assert_eq!(2, game.action_count);
+        }
+
+        #[test]
+        fn fatal_mine_recorded() {
+            let mut game = Game::with_minefield(generate_from_positions(Size2D(3, 3), &[Point2D(2, 1)]).unwrap());
+            game.open(&Point2D(0, 0));
+            assert_eq!(None, game.fatal_mine);
+            game.open(&Point2D(2, 1));
+            assert_eq!(Some(Point2D(2, 1)), game.fatal_mine);
+        }
+
+        struct RecordingSink(Rc<RefCell<Vec<String>>>);
+
+        impl EventSink for RecordingSink {
+            fn on_open(&mut self, location: &Point2D) {
+                self.0.borrow_mut().push(format!("open {} {}", location.0, location.1));
+            }
+
+            fn on_flag(&mut self, location: &Point2D) {
+                self.0.borrow_mut().push(format!("flag {} {}", location.0, location.1));
+            }
+
+            fn on_win(&mut self) {
+                self.0.borrow_mut().push(String::from("win"));
+            }
+
+            fn on_loss(&mut self) {
+                self.0.borrow_mut().push(String::from("loss"));
+            }
+        }
+
+        #[test]
+        fn event_sink_receives_actions() {
+            let events = Rc::new(RefCell::new(Vec::new()));
+            let mut game = Game::with_minefield(MinefieldBuilder::new(Size2D(3, 1)).mine_at(Point2D(2, 0)).build());
+            game.set_event_sink(Box::new(RecordingSink(events.clone())));
+            game.flag(&Point2D(2, 0));
+            game.open(&Point2D(0, 0));
+            game.open(&Point2D(0, 0));
+            game.finish(true);
+            assert_eq!(vec!["flag 2 0", "open 0 0", "win"], *events.borrow());
+            game.finish(false);
+            assert_eq!("loss", events.borrow().last().unwrap());
+        }
+
+        #[test]
+        fn event_sink_survives_restart() {
+            let events = Rc::new(RefCell::new(Vec::new()));
+            let mut game = Game::random(GameSettings::default()).unwrap();
+            game.set_event_sink(Box::new(RecordingSink(events.clone())));
+            let mut restarted = game.restart().unwrap();
+            restarted.flag(&Point2D(0, 0));
+            assert_eq!(vec!["flag 0 0"], *events.borrow());
+            restarted.finish(false);
+            let Some(SystemEvent::ChangeState(mut stats)) = restarted.stats_screen() else {
+                panic!("finished game has no stats screen");
+            };
+            let Some(SystemEvent::ChangeState(mut replayed)) = stats.update(&MockInput::pressing(KeyCode::Char('r')), Duration::ZERO) else {
+                panic!("stats screen did not start a new game");
+            };
+            replayed.update(&MockInput::pressing(KeyCode::Char('f')), Duration::ZERO);
+            assert_eq!("flag 0 0", events.borrow().last().unwrap());
+            assert_eq!(3, events.borrow().len());
+        }
+
+        #[test]
+        fn idle_frame_needs_no_redraw() {
+            let mut game = Game::with_minefield(MinefieldBuilder::new(Size2D(3, 3)).mine_at(Point2D(0, 0)).build());
+            game.needs_redraw = false;
+            game.end_frame(game.frame_snapshot());
+            assert!(!game.needs_redraw());
+            game.flag(&Point2D(1, 1));
+            game.end_frame(game.frame_snapshot());
+            assert!(game.needs_redraw());
+            game.needs_redraw = false;
+            let before = game.frame_snapshot();
+            game.cursor = Point2D(2, 2);
+            game.end_frame(before);
+            assert!(game.needs_redraw());
+        }
+
+        #[test]
+        fn mode_key_makes_open_key_flag() {
+            let mut game = Game::with_minefield(MinefieldBuilder::new(Size2D(2, 2)).mine_at(Point2D(1, 1)).build());
+            game.update(&MockInput::pressing(KeyCode::Char('t')), Duration::ZERO);
+            game.update(&MockInput::pressing(KeyCode::Char(' ')), Duration::ZERO);
+            assert_eq!(Some(CellState::Flagged), game.field.get(&Point2D(0, 0)).map(|cell| cell.state));
+            let mut screen = Screen::new(42, 25);
+            game.draw(&mut screen);
+            let text = |y: i32| (32..42).map(|x| screen.get_pxl(x, y).unwrap().chr).collect::<String>();
+            assert_eq!("Mode: flag", text(24));
+            assert_eq!(" ".repeat(10), text(0));
+        }
+
+        #[test]
+        fn result_keys_replace_replay_prompt() {
+            let mut game = Game::with_minefield(MinefieldBuilder::new(Size2D(2, 2)).mine_at(Point2D(0, 0)).build());
+            game.set_result_keys(vec![String::from("Enter: Summary")]);
+            game.finish(false);
+            let lines = game.result_lines(game.stats().unwrap());
+            assert_eq!(Some("Enter: Summary"), lines.last().map(String::as_str));
+            assert!(!lines.iter().any(|line| line.contains("Replay")));
+        }
+
+        #[test]
+        fn quit_prompt_answers() {
+            let mut game = Game::with_minefield(MinefieldBuilder::new(Size2D(2, 2)).mine_at(Point2D(0, 0)).build());
+            assert!(game.update(&MockInput::pressing(KeyCode::Esc), Duration::ZERO).is_none());
+            assert!(game.confirm_quit);
+            assert!(matches!(game.update(&MockInput::pressing(KeyCode::Char('y')), Duration::ZERO), Some(SystemEvent::Exit)));
+        }
+
+        #[test]
+        fn any_other_key_dismisses_quit_prompt() {
+            let mut game = Game::with_minefield(MinefieldBuilder::new(Size2D(2, 2)).mine_at(Point2D(0, 0)).build());
+            [KeyCode::Down, KeyCode::Char('z')].into_iter().for_each(|key| {
+                assert!(game.update(&MockInput::pressing(KeyCode::Esc), Duration::ZERO).is_none());
+                assert!(game.confirm_quit);
+                assert!(game.update(&MockInput::pressing(key), Duration::ZERO).is_none());
+                assert!(!game.confirm_quit);
+                assert_eq!(Point2D(0, 0), game.cursor);
+            });
+            assert!(game.update(&MockInput::default(), Duration::ZERO).is_none());
+            assert!(!game.confirm_quit);
+        }
+
+        #[test]
+        fn flag_selection() {
+            let mut game = Game::with_minefield(MinefieldBuilder::new(Size2D(3, 3)).mine_at(Point2D(2, 2)).build());
+            game.cursor = Point2D(1, 1);
+            game.selection_anchor = Some(Point2D(0, 0));
+            let cells = game.selected_cells().unwrap();
+            assert_eq!(4, cells.len());
+            game.flag_selection(&cells);
+            assert_eq!("FF.\nFF.\n...", game.field.to_string());
+            assert!(game.selection_anchor.is_none());
+        }
+
+        #[test]
+        fn describe_cursor_cells() {
+            let mut game = Game::with_minefield(MinefieldBuilder::new(Size2D(3, 1))
+                .mine_at(Point2D(0, 0))
+                .mine_at(Point2D(2, 0))
+                .flag(Point2D(2, 0))
+                .build());
+            game.cursor = Point2D(1, 0);
+            assert_eq!("closed", game.cursor_description());
+            game.field.open(&Point2D(1, 0));
+            assert_eq!("2 mines nearby", game.cursor_description());
+            game.cursor = Point2D(2, 0);
+            assert_eq!("flagged", game.cursor_description());
+            game.cursor = Point2D(0, 0);
+            game.field.open(&Point2D(0, 0));
+            assert_eq!("mine", game.cursor_description());
+        }
+
+        #[test]
+        fn opening_flag_leaves_notice() {
+            let mut game = Game::with_minefield(MinefieldBuilder::new(Size2D(2, 1)).flag(Point2D(0, 0)).build());
+            assert_eq!(None, game.open(&Point2D(0, 0)));
+            assert_eq!(Some("Unflag first"), game.notice);
+            game.open(&Point2D(1, 0));
+            assert_eq!(None, game.notice);
+        }
+
+        #[test]
+        fn seed_reproduces_layout() {
+            let settings = GameSettings::default();
+            let game = Game::random(settings.clone()).unwrap();
+            let replayed = Game::seeded(settings, game.seed().unwrap()).unwrap();
+            assert_eq!(game.field.layout().to_string(), replayed.field.layout().to_string());
+        }
+
+        #[test]
+        fn reveal_hint_only_changes_rendering() {
+            let field = generate_from_positions(Size2D(3, 1), &[Point2D(2, 0)]).unwrap();
+            let settings = GameSettings {
+                size: Size2D(3, 1),
+                mine_count: 1,
+                practice: true,
+                ..GameSettings::default()
+            };
+            let mut game = Game::with_settings(field, settings);
+            let states = game.field.cell_states();
+            let plain = game.field.draw_hinted(game.draw_marks()).get_pxl(5, 0).unwrap();
+            game.toggle_reveal_hint();
+            assert!(game.reveal_hint);
+            assert_eq!(states, game.field.cell_states());
+            assert!(plain != game.field.draw_hinted(game.draw_marks()).get_pxl(5, 0).unwrap());
+            assert!(game.stats.is_none());
+        }
+
+        #[test]
+        fn peek_shows_numbers_on_closed_cells() {
+            let settings = GameSettings {
+                practice: true,
+                ..GameSettings::default()
+            };
+            let mut game = Game::with_settings(Minefield::from_pattern("*..").unwrap(), settings);
+            let states = game.field.cell_states();
+            game.toggle_peek();
+            let screen = game.field.draw_hinted(game.draw_marks());
+            assert_eq!(['*', '1', '0'], [0, 1, 2].map(|x| screen.get_pxl(game.field.screen_column(x) as i32, 0).unwrap().chr));
+            assert_eq!(states, game.field.cell_states());
+        }
+
+        #[test]
+        fn reveal_hint_disabled_outside_practice() {
+            let mut game = Game::with_minefield(Minefield::new_empty(&Size2D(3, 3)).unwrap());
+            game.toggle_reveal_hint();
+            assert!(!game.reveal_hint);
+        }
+
+        #[test]
+        fn clock_advances_by_dt_until_finished() {
+            let mut game = Game::with_minefield(Minefield::new_empty(&Size2D(3, 3)).unwrap());
+            [16, 33, 50, 1].iter().for_each(|millis| game.advance_clock(Duration::from_millis(*millis)));
+            assert_eq!(Duration::from_millis(100), game.elapsed);
+            game.finish(true);
+            game.advance_clock(Duration::from_millis(500));
+            assert_eq!(Duration::from_millis(100), game.elapsed);
+            assert_eq!(Duration::from_millis(100), game.stats.unwrap().duration);
+        }
+
+        #[test]
+        fn share_code_regenerates_board() {
+            let game = Game::seeded(GameSettings { size: Size2D(9, 9), mine_count: 10, ..GameSettings::default() }, 42).unwrap();
+            let code = game.share_code().unwrap();
+            let shared = Game::seeded(code.settings(), code.seed).unwrap();
+            assert_eq!(game.field.export(), shared.field.export());
+            assert!(Game::with_minefield(Minefield::new_empty(&Size2D(2, 2)).unwrap()).share_code().is_none());
+        }
+
+        #[test]
+        fn open_records_increasing_timestamps() {
+            // . . . M . . .
+            let mut game = Game::with_minefield(generate_from_positions(Size2D(7, 1), &[Point2D(3, 0)]).unwrap());
+            game.advance_clock(Duration::from_millis(100));
+            game.open(&Point2D(0, 0));
+            game.advance_clock(Duration::from_millis(200));
+            game.open(&Point2D(6, 0));
+            assert_eq!(Some(Duration::from_millis(100)), game.opened_at(&Point2D(0, 0)));
+            assert_eq!(Some(Duration::from_millis(100)), game.opened_at(&Point2D(2, 0)));
+            assert_eq!(Some(Duration::from_millis(300)), game.opened_at(&Point2D(6, 0)));
+            assert_eq!(None, game.opened_at(&Point2D(3, 0)));
+            game.undo();
+            assert_eq!(None, game.opened_at(&Point2D(6, 0)));
+        }
+
+        #[test]
+        fn lives_absorb_mine_hits() {
+            let field = generate_from_positions(Size2D(4, 1), &[Point2D(0, 0), Point2D(3, 0)]).unwrap();
+            let settings = GameSettings {
+                size: Size2D(4, 1),
+                mine_count: 2,
+                lives: 2,
+                ..GameSettings::default()
+            };
+            let mut game = Game::with_settings(field, settings);
+            assert_eq!(Some(CellType::Mine), game.open(&Point2D(0, 0)));
+            game.lose_life();
+            assert_eq!(1, game.lives);
+            assert!(game.explosion_time.is_none());
+            assert!(game.field.get(&Point2D(0, 0)).unwrap().is_open());
+            assert_eq!(Some(CellType::Mine), game.open(&Point2D(3, 0)));
+            game.lose_life();
+            assert_eq!(0, game.lives);
+            assert!(game.explosion_time.is_some());
+        }
+
+        #[test]
+        fn opened_mine_not_undoable() {
             let mut data = Vec2D::sized(&Size2D(2, 1), Cell::default());
+            data.get_mut(&Point2D(1, 0)).unwrap().cell_type = CellType::Mine;
+            let mut game = Game::with_minefield(Minefield::with_data(data));
+            game.open(&Point2D(1, 0));
+            assert!(game.history.is_empty());
+        }
+    }
+
+    mod game_stats {
+        use std::time::Duration;
+        use crate::collections::Vec2D;
+        use crate::game::{Cell, CellState, CellType, GameStats, Minefield};
+        use crate::geom::{Point2D, Size2D};
+
+        #[test]
+        fn from_finished_minefield() {
+            let mut data = Vec2D::sized(&Size2D(3, 1), Cell::default());
             data.get_mut(&Point2D(0, 0)).unwrap().state = CellState::Opened;
+            data.get_mut(&Point2D(1, 0)).unwrap().state = CellState::Opened;
+            data.get_mut(&Point2D(2, 0)).unwrap().cell_type = CellType::Mine;
+            data.get_mut(&Point2D(2, 0)).unwrap().state = CellState::Flagged;
             let minefield = Minefield::with_data(data);
-            assert!(!minefield.only_mines_remaining());
+            let stats = GameStats::from_minefield(&minefield, Duration::from_secs(42), 3, true);
+            assert_eq!(GameStats {
+                duration: Duration::from_secs(42),
+                cells_opened: 2,
+                flags_used: 1,
+                actions: 3,
+                won: true,
+            }, stats);
         }
 
         #[test]
-        fn mines_remaining_mixed() {
+        fn assembled_before_reveal() {
             let mut data = Vec2D::sized(&Size2D(2, 1), Cell::default());
-            data.get_mut(&Point2D(0, 0)).unwrap().state = CellState::Opened;
             data.get_mut(&Point2D(1, 0)).unwrap().cell_type = CellType::Mine;
-            let minefield = Minefield::with_data(data);
-            assert!(minefield.only_mines_remaining());
+            let mut game = crate::game::Game::with_minefield(Minefield::with_data(data));
+            game.open(&Point2D(0, 0));
+            game.finish(true);
+            let stats = game.stats.unwrap();
+            assert_eq!(1, stats.cells_opened);
+            assert!(stats.won);
         }
     }
 
     mod generator {
         use rand::thread_rng;
-        use crate::game::{CellType, RandomMineFieldGenerator};
-        use crate::geom::Size2D;
+        use crate::collections::Vec2D;
+        use crate::game::{block_regions, generate_from_positions, window_mines, CellState, CellType, Game, GameSettings, GenerationError, Minefield, MinefieldBuilder, OpenResult, RandomMineFieldGenerator};
+        use crate::geom::{Point2D, Size2D};
 
         #[test]
         fn generator_puts_correct_number_of_mines() {
             let mut generator = RandomMineFieldGenerator {
                 random: thread_rng(),
             };
-            let minefield = generator.generate(Size2D(10, 10), 15);
+            let minefield = generator.generate(Size2D(10, 10), 15).unwrap();
             let mine_count = minefield.data.all_locations().into_iter()
                 .filter(|location| minefield.get(location).unwrap().cell_type == CellType::Mine)
                 .count();
             assert_eq!(15, mine_count);
         }
+
+        #[test]
+        fn generator_only_places_mines_in_unmasked_cells() {
+            let mut generator = RandomMineFieldGenerator {
+                random: thread_rng(),
+            };
+            let mut mask = Vec2D::sized(&Size2D(3, 3), false);
+            *mask.get_mut(&Point2D(1, 1)).unwrap() = true;
+            *mask.get_mut(&Point2D(2, 1)).unwrap() = true;
+            let minefield = generator.generate_masked(mask, 2).unwrap();
+            let mines = minefield.data.all_locations().into_iter()
+                .filter(|location| minefield.data.get(location).unwrap().cell_type == CellType::Mine)
+                .collect::<Vec<_>>();
+            assert_eq!(vec![Point2D(1, 1), Point2D(2, 1)], mines);
+        }
+
+        #[test]
+        fn generate_from_positions_places_exact_mines() {
+            // M . .
+            // . . M
+            // . . .
+            let minefield = generate_from_positions(Size2D(3, 3), &[Point2D(0, 0), Point2D(2, 1)]).unwrap();
+            assert_eq!(CellType::Mine, minefield.get(&Point2D(0, 0)).unwrap().cell_type);
+            assert_eq!(CellType::Mine, minefield.get(&Point2D(2, 1)).unwrap().cell_type);
+            assert_eq!(2, minefield.count_neighbours(&Point2D(1, 1)));
+            assert_eq!(2, minefield.count_neighbours(&Point2D(1, 0)));
+            assert_eq!(1, minefield.count_neighbours(&Point2D(0, 1)));
+            assert_eq!(1, minefield.count_neighbours(&Point2D(2, 2)));
+            assert_eq!(0, minefield.count_neighbours(&Point2D(0, 2)));
+        }
+
+        #[test]
+        fn generate_from_positions_rejects_out_of_bounds() {
+            let result = generate_from_positions(Size2D(2, 2), &[Point2D(2, 0)]);
+            assert_eq!(Some(GenerationError::OutOfBounds(Point2D(2, 0))), result.err());
+        }
+
+        #[test]
+        fn generate_from_positions_rejects_duplicates() {
+            let result = generate_from_positions(Size2D(2, 2), &[Point2D(1, 1), Point2D(1, 1)]);
+            assert_eq!(Some(GenerationError::DuplicateMine(Point2D(1, 1))), result.err());
+        }
+
+        #[test]
+        fn generator_rejects_zero_dimensions() {
+            let mut generator = RandomMineFieldGenerator {
+                random: thread_rng(),
+            };
+            assert_eq!(Some(GenerationError::EmptySize), generator.generate(Size2D(0, 5), 0).err());
+            assert_eq!(Some(GenerationError::EmptySize), generator.generate(Size2D(5, 0), 0).err());
+            assert_eq!(Some(GenerationError::EmptySize), generate_from_positions(Size2D(0, 5), &[]).err());
+        }
+
+        #[test]
+        fn constructors_reject_zero_dimensions() {
+            assert_eq!(Some(GenerationError::EmptySize), Minefield::new_empty(&Size2D(0, 5)).err());
+            assert_eq!(Some(GenerationError::EmptySize), Minefield::new_empty(&Size2D(5, 0)).err());
+            assert_eq!(Some(GenerationError::EmptySize), Minefield::from_mines(Vec2D::sized(&Size2D(0, 5), false)).err());
+            assert_eq!(1, Minefield::new_empty(&Size2D(1, 1)).unwrap().remaining_safe_cells());
+        }
+
+        #[test]
+        fn balanced_blocks_get_fair_share() {
+            let mut generator = RandomMineFieldGenerator {
+                random: thread_rng(),
+            };
+            let size = Size2D(11, 7);
+            let blocks = Size2D(3, 2);
+            let minefield = generator.generate_balanced(size.clone(), 20, blocks.clone()).unwrap();
+            assert_eq!(20, minefield.mine_count());
+            for (corner, block) in block_regions(&size, &blocks) {
+                let mines = block.iter_points()
+                    .filter(|location| minefield.is_mine(&(location.clone() + &corner)))
+                    .count() as f64;
+                let fair = 20.0 * (block.0 * block.1) as f64 / 77.0;
+                assert!((mines - fair).abs() <= 1.0, "{} mines for a share of {}", mines, fair);
+            }
+            assert_eq!(Some(GenerationError::InvalidBlocks), generator.generate_balanced(Size2D(2, 2), 1, Size2D(3, 1)).err());
+        }
+
+        #[test]
+        fn fixed_positions_are_always_mines() {
+            let mut generator = RandomMineFieldGenerator {
+                random: thread_rng(),
+            };
+            let fixed = [Point2D(2, 2), Point2D(0, 4)];
+            for _ in 0..20 {
+                let minefield = generator.generate_with_fixed(Size2D(5, 5), 10, &fixed).unwrap();
+                assert_eq!(10, minefield.mine_count());
+                assert!(fixed.iter().all(|location| minefield.is_mine(location)));
+            }
+            assert_eq!(Some(GenerationError::TooManyMines), generator.generate_with_fixed(Size2D(5, 5), 1, &fixed).err());
+            assert_eq!(Some(GenerationError::TooManyMines), generator.generate_with_fixed(Size2D(5, 5), 2, &fixed).err());
+            let twice = [Point2D(2, 2), Point2D(2, 2)];
+            assert_eq!(Some(GenerationError::DuplicateMine(Point2D(2, 2))), generator.generate_with_fixed(Size2D(5, 5), 3, &twice).err());
+            assert_eq!(Some(GenerationError::OutOfBounds(Point2D(5, 0))), generator.generate_with_fixed(Size2D(5, 5), 3, &[Point2D(5, 0)]).err());
+        }
+
+        #[test]
+        fn generator_rejects_too_many_mines() {
+            let mut generator = RandomMineFieldGenerator {
+                random: thread_rng(),
+            };
+            assert_eq!(Some(GenerationError::TooManyMines), generator.generate(Size2D(2, 2), 5).err());
+        }
+
+        #[test]
+        fn penalty_picks_only_closed_mines() {
+            let minefield = MinefieldBuilder::new(Size2D(3, 3))
+                .mine_at(Point2D(0, 0))
+                .mine_at(Point2D(1, 1))
+                .mine_at(Point2D(2, 2))
+                .open(Point2D(0, 0))
+                .flag(Point2D(2, 2))
+                .build();
+            for _ in 0..20 {
+                assert_eq!(Some(Point2D(1, 1)), minefield.random_closed_mine(&mut thread_rng()));
+            }
+            assert_eq!(None, Minefield::new_empty(&Size2D(2, 2)).unwrap().random_closed_mine(&mut thread_rng()));
+        }
+
+        #[test]
+        fn open_random_safe_opens_distinct_regions() {
+            // . . M . . . M . .
+            let mut minefield = generate_from_positions(Size2D(9, 1), &[Point2D(2, 0), Point2D(6, 0)]).unwrap();
+            let seeds = minefield.open_random_safe(3, &mut thread_rng());
+            assert_eq!(3, seeds.len());
+            assert!(seeds.iter().enumerate().all(|(idx, seed)| !seeds[..idx].contains(seed)));
+            assert!(seeds.iter().all(|seed| minefield.get(seed).is_some_and(|cell| cell.is_open() && cell.cell_type == CellType::Water)));
+            assert_eq!(0, minefield.opened_mine_count());
+        }
+
+        #[test]
+        fn largest_empty_region() {
+            // . . . M
+            // . . . .
+            // M . . .
+            let minefield = generate_from_positions(Size2D(4, 3), &[Point2D(3, 0), Point2D(0, 2)]).unwrap();
+            assert_eq!(2, minefield.largest_empty_region());
+            let minefield = generate_from_positions(Size2D(4, 3), &[]).unwrap();
+            assert_eq!(12, minefield.largest_empty_region());
+        }
+
+        #[test]
+        fn generator_guarantees_empty_region() {
+            let mut generator = RandomMineFieldGenerator {
+                random: thread_rng(),
+            };
+            let minefield = generator.generate_with_empty_region(Size2D(10, 10), 10, 20).unwrap();
+            assert!(minefield.largest_empty_region() >= 20);
+        }
+
+        #[test]
+        fn generator_limits_largest_opening() {
+            // . . . M
+            // . . . .
+            // M . . .
+            let minefield = generate_from_positions(Size2D(4, 3), &[Point2D(3, 0), Point2D(0, 2)]).unwrap();
+            assert_eq!(6, minefield.largest_opening());
+            let mut generator = RandomMineFieldGenerator {
+                random: thread_rng(),
+            };
+            let minefield = generator.generate_with_max_opening(Size2D(10, 10), 10, 50).unwrap();
+            assert!(minefield.largest_opening() <= 50);
+        }
+
+        #[test]
+        fn largest_opening_counts_shared_numbers_for_each_region() {
+            // The number at (1, 1) borders both the lone empty corner and the large region.
+            let mut minefield = generate_from_positions(Size2D(6, 6), &[Point2D(2, 0), Point2D(0, 2)]).unwrap();
+            assert_eq!(31, minefield.largest_opening());
+            minefield.open(&Point2D(5, 5));
+            assert_eq!(31, minefield.count_in_state(CellState::Opened));
+        }
+
+        #[test]
+        fn max_opening_setting_limits_first_click() {
+            let settings = GameSettings {
+                size: Size2D(10, 10),
+                mine_count: 10,
+                max_opening: Some(0.5),
+                ..GameSettings::default()
+            };
+            for seed in 0..10 {
+                let game = Game::seeded(settings.clone(), seed).unwrap();
+                assert!(game.field.largest_opening() <= 50);
+                game.field.data.size.iter_points().for_each(|location| {
+                    let mut field = game.field.clone();
+                    field.open(&location);
+                    assert!(field.count_in_state(CellState::Opened) <= 50);
+                });
+            }
+        }
+
+        #[test]
+        fn spread_generator_limits_local_density() {
+            let mut generator = RandomMineFieldGenerator {
+                random: thread_rng(),
+            };
+            let minefield = generator.generate_spread(Size2D(10, 10), 30, 5).unwrap();
+            assert_eq!(30, minefield.mine_count());
+            Size2D(10, 10).iter_points().for_each(|centre| {
+                assert!(window_mines(&minefield.data, &centre) <= 5);
+            });
+        }
+
+        #[test]
+        fn generator_gives_up_on_impossible_region() {
+            let mut generator = RandomMineFieldGenerator {
+                random: thread_rng(),
+            };
+            let result = generator.generate_with_empty_region(Size2D(3, 3), 1, 9);
+            assert_eq!(Some(GenerationError::ConstraintUnsatisfied), result.err());
+        }
+
+        #[test]
+        fn single_cell_board() {
+            let mut minefield = generate_from_positions(Size2D(1, 1), &[]).unwrap();
+            assert_eq!(0, minefield.count_neighbours(&Point2D(0, 0)));
+            assert!(!minefield.only_mines_remaining());
+            assert_eq!(OpenResult::Water, minefield.open(&Point2D(0, 0)));
+            assert!(minefield.only_mines_remaining());
+            assert_eq!(3, minefield.draw().get_width());
+        }
     }
-}
\ No newline at end of file
+}
@@ -1,26 +1,128 @@
-use std::cmp::min;
-use std::ops::{Add, Sub};
+use std::cmp::{max, min};
+use std::ops::{Add, AddAssign, Sub};
 use itertools::Itertools;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Size2D(pub usize, pub usize);
 
 #[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Point2D(pub usize, pub usize);
 
+/// Compass direction on the board, with north towards row 0.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Direction {
+    N,
+    NE,
+    E,
+    SE,
+    S,
+    SW,
+    W,
+    NW,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 8] = [
+        Direction::N,
+        Direction::NE,
+        Direction::E,
+        Direction::SE,
+        Direction::S,
+        Direction::SW,
+        Direction::W,
+        Direction::NW,
+    ];
+
+    /// Column and row change of one step in this direction.
+    pub fn offset(&self) -> (isize, isize) {
+        match self {
+            Direction::N => (0, -1),
+            Direction::NE => (1, -1),
+            Direction::E => (1, 0),
+            Direction::SE => (1, 1),
+            Direction::S => (0, 1),
+            Direction::SW => (-1, 1),
+            Direction::W => (-1, 0),
+            Direction::NW => (-1, -1),
+        }
+    }
+
+    pub fn is_orthogonal(&self) -> bool {
+        matches!(self, Direction::N | Direction::E | Direction::S | Direction::W)
+    }
+}
+
+/// Screen column of grid column `x` when columns are `spacing` characters apart,
+/// leaving a character free on the left for the cursor.
+pub fn grid_column(x: usize, spacing: usize) -> usize {
+    x * spacing + 1
+}
+
+/// Characters taken by a grid `columns` wide, including a free character on either side for the cursor.
+pub fn grid_width(columns: usize, spacing: usize) -> usize {
+    grid_column(columns.saturating_sub(1), spacing) + 2
+}
 
 impl Size2D {
     pub fn contains(&self, point: &Point2D) -> bool {
         point.0 < self.0 && point.1 < self.1
     }
+
+    pub fn transpose(&self) -> Size2D {
+        Size2D(self.1, self.0)
+    }
+
+    /// Whether a grid of this size can be drawn on `screen` with every column after the first
+    /// taking `horizontal_spacing` characters, see `grid_width`.
+    pub fn fits_within(&self, screen: &Size2D, horizontal_spacing: usize) -> bool {
+        grid_width(self.0, horizontal_spacing) <= screen.0 && self.1 <= screen.1
+    }
+
+    /// Lazily yields every point within the size, column by column.
+    pub fn iter_points(&self) -> impl Iterator<Item = Point2D> {
+        let height = self.1;
+        (0..self.0).flat_map(move |x| (0..height).map(move |y| Point2D(x, y)))
+    }
 }
 
 impl Point2D {
+    pub fn transpose(&self) -> Point2D {
+        Point2D(self.1, self.0)
+    }
+
     pub fn clip_excl(&mut self, size2d: &Size2D) {
         self.0 = min(self.0, size2d.0.saturating_sub(1));
         self.1 = min(self.1, size2d.1.saturating_sub(1));
     }
 
+    /// Limits both coordinates to the inclusive range between `min` and `max`.
+    pub fn clamp(&mut self, min: &Point2D, max: &Point2D) {
+        self.0 = self.0.clamp(min.0, max.0);
+        self.1 = self.1.clamp(min.1, max.1);
+    }
+
+    /// The adjacent point in `direction`, or `None` when it would have a negative coordinate.
+    pub fn moved(&self, direction: Direction) -> Option<Point2D> {
+        let (x, y) = direction.offset();
+        Some(Point2D(self.0.checked_add_signed(x)?, self.1.checked_add_signed(y)?))
+    }
+
+    /// The next point in `direction`, or `None` when that would leave `size`.
+    pub fn step(&self, direction: Direction, size: &Size2D) -> Option<Point2D> {
+        self.moved(direction).filter(|next| size.contains(next))
+    }
+
+    pub fn chebyshev_distance(&self, other: &Point2D) -> usize {
+        max(self.0.abs_diff(other.0), self.1.abs_diff(other.1))
+    }
+
+    /// Whether `other` is one of the 8 cells surrounding this point.
+    pub fn is_adjacent(&self, other: &Point2D) -> bool {
+        self.chebyshev_distance(other) == 1
+    }
+
     pub fn neighbours(&self) -> Vec<Point2D> {
         (0..=2).cartesian_product(0..=2)
             .map(|(x, y)| Point2D(x, y))
@@ -30,6 +132,55 @@ impl Point2D {
             .filter(|point| !point.eq(self))
             .collect()
     }
+
+    /// Like `neighbours`, with each point tagged by the direction it lies in.
+    pub fn neighbours_tagged(&self) -> Vec<(Direction, Point2D)> {
+        Direction::ALL.into_iter()
+            .filter_map(|direction| self.moved(direction).map(|point| (direction, point)))
+            .collect()
+    }
+
+    /// The surrounding points with coordinates wrapping around the edges of `size`,
+    /// as on a torus. On boards narrower than 3 cells each point is listed once.
+    pub fn wrapped_neighbours(&self, size: &Size2D) -> Vec<Point2D> {
+        if size.0 == 0 || size.1 == 0 {
+            return Vec::new();
+        }
+        let mut neighbours: Vec<Point2D> = Vec::new();
+        (0..=2).cartesian_product(0..=2)
+            .map(|(x, y)| Point2D((self.0 + size.0 + x - 1) % size.0, (self.1 + size.1 + y - 1) % size.1))
+            .for_each(|point| {
+                if !point.eq(self) && !neighbours.contains(&point) {
+                    neighbours.push(point);
+                }
+            });
+        neighbours
+    }
+
+    /// Points on the straight line from this point to `other`, both included, following Bresenham's algorithm.
+    pub fn line_to(&self, other: &Point2D) -> impl Iterator<Item = Point2D> {
+        let (x0, y0) = (self.0 as isize, self.1 as isize);
+        let (x1, y1) = (other.0 as isize, other.1 as isize);
+        let dx = (x1 - x0).abs();
+        let dy = -(y1 - y0).abs();
+        let step_x = if x0 < x1 { 1 } else { -1 };
+        let step_y = if y0 < y1 { 1 } else { -1 };
+        let steps = max(dx, -dy) + 1;
+        let (mut x, mut y, mut error) = (x0, y0, dx + dy);
+        (0..steps).map(move |_| {
+            let point = Point2D(x as usize, y as usize);
+            let doubled = 2 * error;
+            if doubled >= dy {
+                error += dy;
+                x += step_x;
+            }
+            if doubled <= dx {
+                error += dx;
+                y += step_y;
+            }
+            point
+        })
+    }
 }
 
 impl Add<&Point2D> for Point2D {
@@ -40,6 +191,21 @@ impl Add<&Point2D> for Point2D {
     }
 }
 
+impl Add<Point2D> for Point2D {
+    type Output = Point2D;
+
+    fn add(self, rhs: Point2D) -> Self::Output {
+        self + &rhs
+    }
+}
+
+impl AddAssign<&Point2D> for Point2D {
+    fn add_assign(&mut self, rhs: &Point2D) {
+        self.0 += rhs.0;
+        self.1 += rhs.1;
+    }
+}
+
 impl Sub<&Point2D> for Point2D {
     type Output = Point2D;
 
@@ -49,4 +215,12 @@ impl Sub<&Point2D> for Point2D {
             self.1.saturating_sub(rhs.1),
         )
     }
+}
+
+impl Sub<Point2D> for Point2D {
+    type Output = Point2D;
+
+    fn sub(self, rhs: Point2D) -> Self::Output {
+        self - &rhs
+    }
 }
\ No newline at end of file
@@ -0,0 +1,167 @@
+use console_engine::{ConsoleEngine, KeyCode, KeyEventKind, KeyModifiers, MouseButton};
+
+const SPECIAL_KEYS: [KeyCode; 15] = [
+    KeyCode::Left,
+    KeyCode::Right,
+    KeyCode::Up,
+    KeyCode::Down,
+    KeyCode::Enter,
+    KeyCode::Esc,
+    KeyCode::Tab,
+    KeyCode::BackTab,
+    KeyCode::Backspace,
+    KeyCode::Delete,
+    KeyCode::Insert,
+    KeyCode::Home,
+    KeyCode::End,
+    KeyCode::PageUp,
+    KeyCode::PageDown,
+];
+
+/// Keyboard and mouse state and screen size of the current frame, as read by the game states.
+pub trait Input {
+    fn is_key_pressed(&self, key: KeyCode) -> bool;
+
+    fn is_key_pressed_with_modifier(&self, key: KeyCode, modifier: KeyModifiers, kind: KeyEventKind) -> bool;
+
+    fn is_key_held(&self, key: KeyCode) -> bool;
+
+    /// Whether any key the game can tell apart was pressed during this frame, shifted or not.
+    fn is_any_key_pressed(&self) -> bool {
+        (' '..='~').map(KeyCode::Char)
+            .chain(SPECIAL_KEYS)
+            .chain((1..=12).map(KeyCode::F))
+            .any(|key| self.is_key_pressed(key) || self.is_key_pressed_with_modifier(key, KeyModifiers::SHIFT, KeyEventKind::Press))
+    }
+
+    /// Screen cell clicked with `button` during this frame.
+    fn get_mouse_press(&self, button: MouseButton) -> Option<(u32, u32)>;
+
+    /// Screen cell under the mouse while `button` stays down.
+    fn get_mouse_held(&self, button: MouseButton) -> Option<(u32, u32)>;
+
+    fn get_width(&self) -> u32;
+
+    fn get_height(&self) -> u32;
+}
+
+impl Input for ConsoleEngine {
+    fn is_key_pressed(&self, key: KeyCode) -> bool {
+        ConsoleEngine::is_key_pressed(self, key)
+    }
+
+    fn is_key_pressed_with_modifier(&self, key: KeyCode, modifier: KeyModifiers, kind: KeyEventKind) -> bool {
+        ConsoleEngine::is_key_pressed_with_modifier(self, key, modifier, kind)
+    }
+
+    fn is_key_held(&self, key: KeyCode) -> bool {
+        ConsoleEngine::is_key_held(self, key)
+    }
+
+    fn get_mouse_press(&self, button: MouseButton) -> Option<(u32, u32)> {
+        ConsoleEngine::get_mouse_press(self, button)
+    }
+
+    fn get_mouse_held(&self, button: MouseButton) -> Option<(u32, u32)> {
+        ConsoleEngine::get_mouse_held(self, button)
+    }
+
+    fn get_width(&self) -> u32 {
+        ConsoleEngine::get_width(self)
+    }
+
+    fn get_height(&self) -> u32 {
+        ConsoleEngine::get_height(self)
+    }
+}
+
+/// Printable character typed this frame, with or without Shift.
+pub fn typed_char(input: &dyn Input) -> Option<char> {
+    (' '..='~').find(|c| {
+        input.is_key_pressed(KeyCode::Char(*c))
+            || input.is_key_pressed_with_modifier(KeyCode::Char(*c), KeyModifiers::SHIFT, KeyEventKind::Press)
+    })
+}
+
+/// Scripted input for a single frame. Pressed keys and clicks also count as held, like in a terminal.
+#[cfg(test)]
+#[derive(Debug, Clone)]
+pub struct MockInput {
+    pressed: Vec<(KeyCode, KeyModifiers)>,
+    clicks: Vec<(MouseButton, u32, u32)>,
+    width: u32,
+    height: u32,
+}
+
+#[cfg(test)]
+impl Default for MockInput {
+    fn default() -> Self {
+        Self {
+            pressed: Vec::new(),
+            clicks: Vec::new(),
+            width: 42,
+            height: 25,
+        }
+    }
+}
+
+#[cfg(test)]
+impl MockInput {
+    pub fn pressing(key: KeyCode) -> Self {
+        Self::default().press(key)
+    }
+
+    pub fn press(mut self, key: KeyCode) -> Self {
+        self.pressed.push((key, KeyModifiers::NONE));
+        self
+    }
+
+    pub fn press_shifted(mut self, key: KeyCode) -> Self {
+        self.pressed.push((key, KeyModifiers::SHIFT));
+        self
+    }
+
+    pub fn click(mut self, button: MouseButton, x: u32, y: u32) -> Self {
+        self.clicks.push((button, x, y));
+        self
+    }
+
+    pub fn sized(mut self, width: u32, height: u32) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+}
+
+#[cfg(test)]
+impl Input for MockInput {
+    fn is_key_pressed(&self, key: KeyCode) -> bool {
+        self.is_key_pressed_with_modifier(key, KeyModifiers::NONE, KeyEventKind::Press)
+    }
+
+    fn is_key_pressed_with_modifier(&self, key: KeyCode, modifier: KeyModifiers, _kind: KeyEventKind) -> bool {
+        self.pressed.contains(&(key, modifier))
+    }
+
+    fn is_key_held(&self, key: KeyCode) -> bool {
+        self.is_key_pressed(key)
+    }
+
+    fn get_mouse_press(&self, button: MouseButton) -> Option<(u32, u32)> {
+        self.clicks.iter()
+            .find(|(clicked, _, _)| *clicked == button)
+            .map(|(_, x, y)| (*x, *y))
+    }
+
+    fn get_mouse_held(&self, button: MouseButton) -> Option<(u32, u32)> {
+        self.get_mouse_press(button)
+    }
+
+    fn get_width(&self) -> u32 {
+        self.width
+    }
+
+    fn get_height(&self) -> u32 {
+        self.height
+    }
+}
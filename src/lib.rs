@@ -0,0 +1,376 @@
+pub mod bindings;
+pub mod campaign;
+pub mod collections;
+pub mod config;
+pub mod editor;
+pub mod events;
+pub mod game;
+pub mod geom;
+pub mod input;
+pub mod main_menu;
+pub mod render;
+pub mod replay;
+pub mod share;
+pub mod state;
+pub mod stats;
+
+#[cfg(test)]
+mod tests {
+
+    mod size2d {
+        use crate::collections::Vec2D;
+        use crate::geom::{Point2D, Size2D};
+
+        #[test]
+        fn contains_point() {
+            let size = Size2D(3, 4);
+            let point = Point2D(2, 3);
+            assert!(size.contains(&point));
+        }
+
+        #[test]
+        fn not_contains_width() {
+            let size = Size2D(3, 4);
+            let point = Point2D(3, 3);
+            assert!(!size.contains(&point));
+        }
+
+        #[test]
+        fn not_contains_height() {
+            let size = Size2D(3, 4);
+            let point = Point2D(2, 4);
+            assert!(!size.contains(&point));
+        }
+
+        #[test]
+        fn transpose() {
+            let size = Size2D(2, 3).transpose();
+            assert_eq!((3, 2), (size.0, size.1));
+        }
+
+        #[test]
+        fn iter_points_matches_all_locations() {
+            let size = Size2D(3, 2);
+            let points = size.iter_points().collect::<Vec<_>>();
+            assert_eq!(Vec2D::sized(&size, 0).all_locations(), points);
+            assert_eq!(Point2D(0, 1), points[1]);
+        }
+
+        #[cfg(feature = "serde")]
+        #[test]
+        fn serde_round_trip() {
+            let json = serde_json::to_string(&Size2D(16, 10)).unwrap();
+            assert_eq!(Size2D(16, 10), serde_json::from_str::<Size2D>(&json).unwrap());
+        }
+
+        #[test]
+        fn board_that_just_fits() {
+            assert!(Size2D(20, 23).fits_within(&Size2D(41, 23), 2));
+            assert!(Size2D(40, 10).fits_within(&Size2D(42, 10), 1));
+        }
+
+        #[test]
+        fn board_one_column_too_wide() {
+            assert!(!Size2D(21, 23).fits_within(&Size2D(41, 23), 2));
+            assert!(!Size2D(41, 10).fits_within(&Size2D(42, 10), 1));
+        }
+
+        #[test]
+        fn board_too_tall() {
+            assert!(!Size2D(5, 11).fits_within(&Size2D(42, 10), 2));
+        }
+    }
+
+    mod point2d {
+        use crate::geom::{Direction, Point2D, Size2D};
+
+        #[test]
+        fn corner_has_three_tagged_neighbours() {
+            let directions = Point2D(0, 0).neighbours_tagged().into_iter()
+                .map(|(direction, _)| direction)
+                .collect::<Vec<_>>();
+            assert_eq!(vec![Direction::E, Direction::SE, Direction::S], directions);
+        }
+
+        #[test]
+        fn interior_has_every_tagged_neighbour() {
+            let tagged = Point2D(1, 1).neighbours_tagged();
+            assert_eq!(Direction::ALL.to_vec(), tagged.iter().map(|(direction, _)| *direction).collect::<Vec<_>>());
+            assert!(tagged.contains(&(Direction::NW, Point2D(0, 0))));
+            assert!(tagged.contains(&(Direction::S, Point2D(1, 2))));
+            assert_eq!(4, tagged.iter().filter(|(direction, _)| direction.is_orthogonal()).count());
+        }
+
+        #[test]
+        fn add_points() {
+            let p1 = Point2D(1, 2);
+            let p2 = Point2D(3, 4);
+            assert_eq!(Point2D(4, 6), p1 + &p2);
+        }
+
+        #[test]
+        fn add_owned_points() {
+            let p1 = Point2D(1, 2);
+            let p2 = Point2D(3, 4);
+            assert_eq!(Point2D(4, 6), p1 + p2);
+        }
+
+        #[test]
+        fn add_assign_point() {
+            let mut point = Point2D(1, 2);
+            point += &Point2D(3, 4);
+            assert_eq!(Point2D(4, 6), point);
+        }
+
+        #[test]
+        fn clamp_below_min() {
+            let mut point = Point2D(0, 1);
+            point.clamp(&Point2D(2, 2), &Point2D(5, 5));
+            assert_eq!(Point2D(2, 2), point);
+        }
+
+        #[test]
+        fn clamp_above_max() {
+            let mut point = Point2D(9, 7);
+            point.clamp(&Point2D(2, 2), &Point2D(5, 5));
+            assert_eq!(Point2D(5, 5), point);
+        }
+
+        #[test]
+        fn clamp_inside() {
+            let mut point = Point2D(3, 5);
+            point.clamp(&Point2D(2, 2), &Point2D(5, 5));
+            assert_eq!(Point2D(3, 5), point);
+        }
+
+        #[test]
+        fn horizontal_line() {
+            let points = Point2D(4, 2).line_to(&Point2D(1, 2)).collect::<Vec<_>>();
+            assert_eq!(vec![Point2D(4, 2), Point2D(3, 2), Point2D(2, 2), Point2D(1, 2)], points);
+        }
+
+        #[test]
+        fn diagonal_line() {
+            let points = Point2D(0, 3).line_to(&Point2D(3, 0)).collect::<Vec<_>>();
+            assert_eq!(vec![Point2D(0, 3), Point2D(1, 2), Point2D(2, 1), Point2D(3, 0)], points);
+        }
+
+        #[test]
+        fn steep_line_includes_endpoints() {
+            let points = Point2D(1, 0).line_to(&Point2D(2, 4)).collect::<Vec<_>>();
+            assert_eq!(5, points.len());
+            assert_eq!(Some(&Point2D(1, 0)), points.first());
+            assert_eq!(Some(&Point2D(2, 4)), points.last());
+        }
+
+        #[test]
+        fn wrapped_neighbours_of_corner() {
+            let neighbours = Point2D(0, 0).wrapped_neighbours(&Size2D(3, 3));
+            assert_eq!(8, neighbours.len());
+            assert!(neighbours.contains(&Point2D(2, 2)));
+            assert!(neighbours.contains(&Point2D(0, 2)));
+            assert!(neighbours.contains(&Point2D(2, 0)));
+            assert!(!neighbours.contains(&Point2D(0, 0)));
+        }
+
+        #[test]
+        fn sub_points() {
+            let p1 = Point2D(5, 3);
+            let p2 = Point2D(2, 4);
+            assert_eq!(Point2D(3, 0), p1.clone() - &p2);
+            assert_eq!(Point2D(3, 0), p1 - p2);
+        }
+
+        #[test]
+        fn clip() {
+            let mut point = Point2D(10, 10);
+            let size = Size2D(6, 8);
+            point.clip_excl(&size);
+            assert_eq!(Point2D(5, 7), point);
+        }
+
+        #[test]
+        fn all_neighbours() {
+            let point = Point2D(1, 1);
+            assert_eq!(8, point.neighbours().len());
+        }
+
+        #[test]
+        fn transpose() {
+            assert_eq!(Point2D(4, 1), Point2D(1, 4).transpose());
+        }
+
+        #[cfg(feature = "serde")]
+        #[test]
+        fn serde_round_trip() {
+            let json = serde_json::to_string(&Point2D(3, 7)).unwrap();
+            assert_eq!("[3,7]", json);
+            assert_eq!(Point2D(3, 7), serde_json::from_str::<Point2D>(&json).unwrap());
+        }
+
+        #[test]
+        fn chebyshev_distance() {
+            assert_eq!(0, Point2D(2, 2).chebyshev_distance(&Point2D(2, 2)));
+            assert_eq!(3, Point2D(2, 2).chebyshev_distance(&Point2D(5, 1)));
+            assert_eq!(2, Point2D(2, 2).chebyshev_distance(&Point2D(0, 4)));
+        }
+
+        #[test]
+        fn adjacent_diagonal() {
+            assert!(Point2D(1, 1).is_adjacent(&Point2D(2, 2)));
+            assert!(Point2D(1, 1).is_adjacent(&Point2D(0, 0)));
+        }
+
+        #[test]
+        fn adjacent_orthogonal() {
+            assert!(Point2D(1, 1).is_adjacent(&Point2D(1, 0)));
+            assert!(Point2D(1, 1).is_adjacent(&Point2D(2, 1)));
+        }
+
+        #[test]
+        fn not_adjacent_to_self() {
+            assert!(!Point2D(1, 1).is_adjacent(&Point2D(1, 1)));
+        }
+
+        #[test]
+        fn not_adjacent_distant() {
+            assert!(!Point2D(1, 1).is_adjacent(&Point2D(3, 1)));
+            assert!(!Point2D(0, 0).is_adjacent(&Point2D(2, 2)));
+        }
+
+        #[test]
+        fn neighbours_for_origin() {
+            let point = Point2D::default();
+            assert_eq!(3, point.neighbours().len());
+        }
+    }
+
+    mod vec2d {
+        use crate::collections::Vec2D;
+        use crate::geom::{Point2D, Size2D};
+
+        #[test]
+        fn index_in_bounds() {
+            let mut v = Vec2D::from_fn(&Size2D(3, 2), |point| point.0 + 10 * point.1);
+            assert_eq!(12, v[&Point2D(2, 1)]);
+            v[&Point2D(0, 1)] = 7;
+            assert_eq!(Some(&7), v.get(&Point2D(0, 1)));
+        }
+
+        #[test]
+        #[should_panic]
+        fn index_out_of_bounds_panics() {
+            let v = Vec2D::sized(&Size2D(3, 2), 0);
+            let _ = v[&Point2D(0, 2)];
+        }
+
+        #[test]
+        fn resize_keeps_overlapping_values() {
+            let mut v = Vec2D::from_fn(&Size2D(2, 2), |point| point.0 + 10 * point.1);
+            v.resize(&Size2D(3, 3), 99);
+            assert_eq!(Size2D(3, 3), v.size);
+            assert_eq!(vec![&0, &1, &99], v.row(0).unwrap().collect::<Vec<_>>());
+            assert_eq!(vec![&10, &11, &99], v.row(1).unwrap().collect::<Vec<_>>());
+            assert_eq!(vec![&99, &99, &99], v.row(2).unwrap().collect::<Vec<_>>());
+            v.resize(&Size2D(2, 2), 0);
+            assert_eq!(Size2D(2, 2), v.size);
+            assert_eq!(None, v.get(&Point2D(2, 0)));
+            v.size.iter_points().for_each(|point| {
+                assert_eq!(point.0 + 10 * point.1, v[&point]);
+            });
+        }
+
+        #[test]
+        fn get_value_in_single_cell_vec2d() {
+            let v = Vec2D::sized(&Size2D(1, 1), 5);
+            assert_eq!(&5, v.get(&Point2D(0, 0)).unwrap());
+        }
+
+        #[test]
+        fn rotate_cw_moves_left_column_to_top() {
+            let v = Vec2D::from_fn(&Size2D(2, 3), |point| point.0 * 10 + point.1);
+            let rotated = v.rotate_cw();
+            assert_eq!(Size2D(3, 2), rotated.size);
+            assert_eq!(vec![&2, &1, &0], rotated.row(0).unwrap().collect::<Vec<_>>());
+            assert_eq!(v.size, rotated.rotate_ccw().size);
+            v.size.iter_points().for_each(|point| {
+                assert_eq!(v.get(&point), rotated.rotate_ccw().get(&point));
+            });
+        }
+
+        #[test]
+        fn half_turn_equals_both_flips() {
+            let v = Vec2D::from_fn(&Size2D(2, 3), |point| point.0 * 10 + point.1);
+            let turned = v.rotate_cw().rotate_cw();
+            let flipped = v.flip_horizontal().flip_vertical();
+            assert_eq!(v.size, turned.size);
+            v.size.iter_points().for_each(|point| {
+                assert_eq!(flipped.get(&point), turned.get(&point));
+            });
+            assert_eq!(&0, turned.get(&Point2D(1, 2)).unwrap());
+            assert_eq!(&12, turned.get(&Point2D(0, 0)).unwrap());
+        }
+
+        #[test]
+        fn row_and_column() {
+            let v = Vec2D::from_fn(&Size2D(3, 2), |point| point.0 * 10 + point.1);
+            assert_eq!(vec![&1, &11, &21], v.row(1).unwrap().collect::<Vec<_>>());
+            assert_eq!(vec![&20, &21], v.column(2).unwrap().collect::<Vec<_>>());
+            assert!(v.row(2).is_none());
+            assert!(v.column(3).is_none());
+        }
+
+        #[test]
+        fn neighbours_at_corner() {
+            let v = Vec2D::from_fn(&Size2D(3, 3), |point| point.0 * 10 + point.1);
+            let mut neighbours = v.neighbours(&Point2D(2, 0));
+            neighbours.sort();
+            assert_eq!(vec![&10, &11, &21], neighbours);
+        }
+
+        #[test]
+        fn from_fn() {
+            let v = Vec2D::from_fn(&Size2D(2, 3), |point| point.0 * 10 + point.1);
+            assert_eq!(&12, v.get(&Point2D(1, 2)).unwrap());
+        }
+
+        #[test]
+        fn transpose() {
+            let v = Vec2D::from_fn(&Size2D(2, 3), |point| point.0 * 10 + point.1);
+            let t = v.transpose();
+            assert_eq!((3, 2), (t.size.0, t.size.1));
+            v.all_locations().iter().for_each(|point| {
+                assert_eq!(v.get(point), t.get(&point.transpose()));
+            });
+            assert_eq!(&12, t.get(&Point2D(2, 1)).unwrap());
+        }
+
+        #[test]
+        fn swap() {
+            let mut v = Vec2D::from_fn(&Size2D(2, 3), |point| point.0 * 10 + point.1);
+            v.swap(&Point2D(0, 1), &Point2D(1, 2));
+            assert_eq!(&12, v.get(&Point2D(0, 1)).unwrap());
+            assert_eq!(&1, v.get(&Point2D(1, 2)).unwrap());
+            v.swap(&Point2D(1, 0), &Point2D(1, 1));
+            assert_eq!(&11, v.get(&Point2D(1, 0)).unwrap());
+            assert_eq!(&10, v.get(&Point2D(1, 1)).unwrap());
+        }
+
+        #[test]
+        fn swap_out_of_bounds_is_noop() {
+            let mut v = Vec2D::from_fn(&Size2D(2, 2), |point| point.0 * 10 + point.1);
+            v.swap(&Point2D(0, 0), &Point2D(2, 0));
+            v.swap(&Point2D(0, 5), &Point2D(1, 1));
+            assert_eq!(&0, v.get(&Point2D(0, 0)).unwrap());
+            assert_eq!(&11, v.get(&Point2D(1, 1)).unwrap());
+        }
+
+        #[test]
+        fn fill() {
+            let mut v = Vec2D::from_fn(&Size2D(2, 3), |point| point.0 * 10 + point.1);
+            v.fill(7);
+            assert!(v.all_locations().iter().all(|point| v.get(point) == Some(&7)));
+        }
+    }
+
+}
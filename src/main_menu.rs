@@ -1,40 +1,98 @@
-use console_engine::{Color, ConsoleEngine, KeyCode};
+use std::cmp::{max, min};
+use std::time::Duration;
+use console_engine::{Color, KeyCode, KeyEventKind, KeyModifiers};
 use console_engine::pixel::pxl;
 use console_engine::screen::Screen;
-use rand::thread_rng;
-use crate::game::{Game, RandomMineFieldGenerator};
+use crate::campaign::{Campaign, Level};
+use crate::editor::Editor;
+use crate::game::{clock_seed, fitting_board_size, Game, GameSettings};
 use crate::geom::Size2D;
+use crate::input::{typed_char, Input};
+use crate::share::ShareCode;
 use crate::state::{GameState, SystemEvent};
 
 const MAIN_MENU_HEADER: &str = include_str!("../assets/main_menu_header.txt");
+const MAIN_MENU_TITLE: &str = "BOMBE";
+const MENU_WIDTH: i32 = 13;
+/// Rows from the width setting down to the share code line.
+const MENU_HEIGHT: i32 = 14;
+const MAX_LIVES: u8 = 9;
+/// Change applied by Shift+Left/Right.
+const FAST_STEP: i32 = 10;
+const KEY_EDITOR: KeyCode = KeyCode::Char('e');
+const KEY_CAMPAIGN: KeyCode = KeyCode::Char('c');
+const KEY_PRACTICE: KeyCode = KeyCode::Char('p');
+const KEY_LOAD_CODE: KeyCode = KeyCode::Char('l');
 const RAINBOW_COLORS: [Color; 6] = [Color::Blue, Color::Cyan, Color::Green, Color::Yellow, Color::Red, Color::Magenta];
+/// Time each header line keeps its color before the rainbow moves on by one.
+const RAINBOW_STEP: Duration = Duration::from_millis(500);
 
 #[derive(Debug, Clone, PartialEq)]
 enum MainMenuCursorPosition {
     Width = 0,
     Height,
     MineCount,
+    Lives,
     StartGame,
 }
 
 impl MainMenuCursorPosition {
+    const ALL: [MainMenuCursorPosition; 5] = [
+        MainMenuCursorPosition::Width,
+        MainMenuCursorPosition::Height,
+        MainMenuCursorPosition::MineCount,
+        MainMenuCursorPosition::Lives,
+        MainMenuCursorPosition::StartGame,
+    ];
+
+    fn from_index(index: usize) -> MainMenuCursorPosition {
+        Self::ALL[index % Self::ALL.len()].clone()
+    }
+
+    fn to_index(&self) -> usize {
+        self.clone() as usize
+    }
+
+    fn cycle(&self, delta: i32) -> MainMenuCursorPosition {
+        Self::from_index(wrapping_offset(self.to_index(), delta, Self::ALL.len()))
+    }
+
     fn next(&self) -> MainMenuCursorPosition {
-        use MainMenuCursorPosition::*;
-        match self {
-            Width => Height,
-            Height => MineCount,
-            MineCount => StartGame,
-            StartGame => Width,
-        }
+        self.cycle(1)
     }
 
     fn prev(&self) -> MainMenuCursorPosition {
-        use MainMenuCursorPosition::*;
-        match self {
-            StartGame => MineCount,
-            MineCount => Height,
-            Height => Width,
-            Width => StartGame,
+        self.cycle(-1)
+    }
+}
+
+/// Moves `index` by `delta` within `0..len`, wrapping around at both ends.
+fn wrapping_offset(index: usize, delta: i32, len: usize) -> usize {
+    (index as i64 + delta as i64).rem_euclid(len as i64) as usize
+}
+
+/// Color of header line `line` after the menu has been shown for `elapsed`.
+fn rainbow_color(line: usize, elapsed: Duration) -> Color {
+    let shift = (elapsed.as_millis() / RAINBOW_STEP.as_millis()) as usize;
+    RAINBOW_COLORS[(line + shift) % RAINBOW_COLORS.len()]
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum HeaderVariant {
+    Art,
+    Plain,
+}
+
+impl HeaderVariant {
+    fn for_screen(width: u32, height: u32) -> HeaderVariant {
+        let header_width = MAIN_MENU_HEADER.lines().map(|line| line.len()).max().unwrap_or(0);
+        let header_height = MAIN_MENU_HEADER.lines().count();
+        let menu_offset_y = height as i32 / 2 - MENU_HEIGHT / 2;
+        let space_above_menu = max(menu_offset_y - 3, 0) as usize;
+        if header_width <= width as usize && header_height <= space_above_menu {
+            HeaderVariant::Art
+        } else {
+            HeaderVariant::Plain
         }
     }
 }
@@ -44,73 +102,492 @@ pub struct MainMenu {
     width: usize,
     height: usize,
     mine_count: usize,
+    lives: u8,
+    /// Dimensions of the terminal, known after the first update.
+    screen: Option<Size2D>,
+    /// Share code being typed in, see `ShareCode`.
+    code_input: Option<String>,
+    invalid_code: bool,
+    /// Whether the player changed the board settings, which stops suggesting a board for the screen.
+    customized: bool,
+    /// Time since the menu was opened, for cycling the header colors.
+    elapsed: Duration,
 }
 
 impl Default for MainMenu {
     fn default() -> Self {
         Self {
             cursor_position: MainMenuCursorPosition::StartGame,
-            width: 10,
+            width: 16,
             height: 10,
-            mine_count: 10,
+            mine_count: 20,
+            lives: 1,
+            screen: None,
+            code_input: None,
+            invalid_code: false,
+            customized: false,
+            elapsed: Duration::ZERO,
         }
     }
 }
 
 impl MainMenu {
-    fn start_game(&self) -> SystemEvent {
-        let minefield = RandomMineFieldGenerator {
-            random: thread_rng(),
-        }.generate(Size2D(self.width, self.height), self.mine_count);
-        let game = Game::with_minefield(minefield);
-        SystemEvent::ChangeState(Box::new(game))
+    fn start_game(&self, practice: bool) -> Option<SystemEvent> {
+        let settings = GameSettings {
+            size: Size2D(self.width, self.height),
+            mine_count: self.mine_count,
+            lives: self.lives,
+            practice,
+            ..GameSettings::default()
+        };
+        Game::seeded(settings, clock_seed()).ok()
+            .map(|game| SystemEvent::ChangeState(Box::new(game)))
+    }
+
+    fn adjust(&mut self, delta: i32) {
+        let apply = |value: usize| (value as i64 + delta as i64).clamp(0, u8::MAX as i64) as usize;
+        match self.cursor_position {
+            MainMenuCursorPosition::Width => self.width = apply(self.width),
+            MainMenuCursorPosition::Height => self.height = apply(self.height),
+            MainMenuCursorPosition::MineCount => self.mine_count = apply(self.mine_count),
+            MainMenuCursorPosition::Lives => self.lives = apply(self.lives as usize) as u8,
+            MainMenuCursorPosition::StartGame => {}
+        }
+        self.customized = true;
+        self.clamp_values();
+    }
+
+    /// Handles a frame of typing a share code, starting the shared board on Enter.
+    fn update_code_input(&mut self, input: &dyn Input) -> Option<SystemEvent> {
+        if input.is_key_pressed(KeyCode::Esc) {
+            self.code_input = None;
+            return None;
+        }
+        if input.is_key_pressed(KeyCode::Enter) {
+            return self.submit_code();
+        }
+        let code = self.code_input.as_mut()?;
+        if input.is_key_pressed(KeyCode::Backspace) {
+            code.pop();
+        }
+        if let Some(typed) = typed_char(input) {
+            code.push(typed);
+        }
+        None
+    }
+
+    fn submit_code(&mut self) -> Option<SystemEvent> {
+        let game = self.code_input.as_deref()
+            .and_then(ShareCode::parse)
+            .and_then(|code| Game::seeded(code.settings(), code.seed).ok());
+        self.invalid_code = game.is_none();
+        Some(SystemEvent::ChangeState(Box::new(game?)))
+    }
+
+    /// Shrinks the board to fit a terminal of `screen` whenever its size changes. Until the
+    /// player changes the settings, the board is replaced by a suggestion fitting the screen.
+    fn set_screen(&mut self, screen: Size2D) {
+        if self.screen.as_ref() != Some(&screen) {
+            if !self.customized {
+                self.suggest_board(&fitting_board_size(&screen));
+            }
+            self.screen = Some(screen);
+            self.clamp_values();
+        }
+    }
+
+    /// Picks the default board if it fits within `fitting`, otherwise the largest campaign
+    /// board that does. Screens too small for any of them get the default density.
+    fn suggest_board(&mut self, fitting: &Size2D) {
+        let default = MainMenu::default();
+        let default_level = Level::new(Size2D(default.width, default.height), default.mine_count);
+        let suggestion = Level::default_levels().into_iter()
+            .chain(std::iter::once(default_level))
+            .filter(|level| level.size.0 <= default.width && level.size.1 <= default.height)
+            .filter(|level| level.size.0 <= fitting.0 && level.size.1 <= fitting.1)
+            .max_by_key(|level| level.size.0 * level.size.1);
+        match suggestion {
+            Some(level) => {
+                self.width = level.size.0;
+                self.height = level.size.1;
+                self.mine_count = level.mine_count;
+            }
+            None => {
+                self.width = min(default.width, fitting.0);
+                self.height = min(default.height, fitting.1);
+                self.mine_count = default.mine_count * self.width * self.height / (default.width * default.height);
+            }
+        }
+    }
+
+    /// Keeps the board at least 1x1 and within the screen, with at least one safe cell and one life.
+    fn clamp_values(&mut self) {
+        if let Some(fitting) = self.screen.as_ref().map(fitting_board_size) {
+            self.width = min(self.width, fitting.0);
+            self.height = min(self.height, fitting.1);
+        }
+        self.width = max(self.width, 1);
+        self.height = max(self.height, 1);
+        self.mine_count = min(self.mine_count, self.width * self.height - 1);
+        self.lives = self.lives.clamp(1, MAX_LIVES);
     }
 }
 
 impl GameState for MainMenu {
-    fn update(&mut self, engine: &ConsoleEngine) -> Option<SystemEvent> {
-        if engine.is_key_pressed(KeyCode::Esc) {
+    fn update(&mut self, input: &dyn Input, dt: Duration) -> Option<SystemEvent> {
+        self.elapsed += dt;
+        if self.code_input.is_some() {
+            return self.update_code_input(input);
+        }
+        if input.is_key_pressed(KeyCode::Esc) {
             return Some(SystemEvent::Exit);
         }
-        if engine.is_key_pressed(KeyCode::Up) {
+        self.set_screen(Size2D(input.get_width() as usize, input.get_height() as usize));
+        if input.is_key_pressed(KeyCode::Up) {
             self.cursor_position = self.cursor_position.prev();
         }
-        if engine.is_key_pressed(KeyCode::Down) {
+        if input.is_key_pressed(KeyCode::Down) {
             self.cursor_position = self.cursor_position.next();
         }
-        if self.cursor_position == MainMenuCursorPosition::StartGame && engine.is_key_pressed(KeyCode::Enter) {
-            return Some(self.start_game());
+        if input.is_key_pressed(KeyCode::Left) {
+            self.adjust(-1);
+        }
+        if input.is_key_pressed(KeyCode::Right) {
+            self.adjust(1);
+        }
+        if input.is_key_pressed_with_modifier(KeyCode::Left, KeyModifiers::SHIFT, KeyEventKind::Press) {
+            self.adjust(-FAST_STEP);
+        }
+        if input.is_key_pressed_with_modifier(KeyCode::Right, KeyModifiers::SHIFT, KeyEventKind::Press) {
+            self.adjust(FAST_STEP);
+        }
+        if input.is_key_pressed(KEY_EDITOR) {
+            return Editor::new(&Size2D(self.width, self.height)).ok()
+                .map(|editor| SystemEvent::ChangeState(Box::new(editor)));
+        }
+        if input.is_key_pressed(KEY_CAMPAIGN) {
+            return Campaign::new(Level::default_levels())
+                .map(|campaign| SystemEvent::ChangeState(Box::new(campaign)));
+        }
+        if input.is_key_pressed(KEY_PRACTICE) {
+            return self.start_game(true);
+        }
+        if input.is_key_pressed(KEY_LOAD_CODE) {
+            self.code_input = Some(String::new());
+            self.invalid_code = false;
+        }
+        if self.cursor_position == MainMenuCursorPosition::StartGame && input.is_key_pressed(KeyCode::Enter) {
+            return self.start_game(false);
         }
         None
     }
 
     fn draw(&self, screen: &mut Screen) {
-        const WIDTH: i32 = 13;
-        const HEIGHT: i32 = 7;
-
         let center_x = screen.get_width() as i32 / 2;
         let center_y = screen.get_height() as i32 / 2;
-        let offset_x = center_x - WIDTH / 2;
-        let offset_y = center_y - HEIGHT / 2;
+        let offset_x = center_x - MENU_WIDTH / 2;
+        let offset_y = center_y - MENU_HEIGHT / 2;
         let text_x = offset_x + 2;
 
-        let header_width = MAIN_MENU_HEADER.lines().map(|line| line.len()).max().unwrap_or(0) as u32;
-        let header_height = MAIN_MENU_HEADER.lines().count() as u32;
-
-        if screen.get_width() >= header_width && offset_y > (header_height + 2) as i32 {
-            let offset_x = center_x - header_width as i32 / 2;
-            let offset_y = offset_y / 2 - header_height as i32 / 2;
-            MAIN_MENU_HEADER.lines().enumerate().for_each(|(idx, line)| {
-                let color = RAINBOW_COLORS.get(idx % RAINBOW_COLORS.len()).unwrap();
-                screen.print_fbg(offset_x, offset_y + idx as i32, line, *color, Color::Reset);
-            });
-            // screen.print(center_x - header_width as i32 / 2, offset_y / 2 - header_height as i32 / 2, MAIN_MENU_HEADER);
+        match HeaderVariant::for_screen(screen.get_width(), screen.get_height()) {
+            HeaderVariant::Art => {
+                let header_width = MAIN_MENU_HEADER.lines().map(|line| line.len()).max().unwrap_or(0) as i32;
+                let header_height = MAIN_MENU_HEADER.lines().count() as i32;
+                let offset_x = center_x - header_width / 2;
+                let offset_y = offset_y / 2 - header_height / 2;
+                MAIN_MENU_HEADER.lines().enumerate().for_each(|(idx, line)| {
+                    screen.print_fbg(offset_x, offset_y + idx as i32, line, rainbow_color(idx, self.elapsed), Color::Reset);
+                });
+            }
+            HeaderVariant::Plain => {
+                let title_x = center_x - MAIN_MENU_TITLE.len() as i32 / 2;
+                let title_y = max(offset_y - 2, 0);
+                screen.print_fbg(title_x, title_y, MAIN_MENU_TITLE, rainbow_color(0, self.elapsed), Color::Reset);
+            }
         }
 
         screen.print(text_x, offset_y, &format!("Width: {}", self.width));
         screen.print(text_x, offset_y + 2, &format!("Height: {}", self.height));
         screen.print(text_x, offset_y + 4, &format!("Mines: {}", self.mine_count));
-        screen.print(text_x, offset_y + 6, "Start Game");
-        screen.set_pxl(offset_x, offset_y + self.cursor_position.clone() as i32 * 2, pxl('*'))
+        screen.print(text_x, offset_y + 6, &format!("Lives: {}", self.lives));
+        screen.print(text_x, offset_y + 8, "Start Game");
+        screen.print(text_x, offset_y + 10, "E: Editor");
+        screen.print(text_x, offset_y + 11, "C: Campaign");
+        screen.print(text_x, offset_y + 12, "P: Practice");
+        match &self.code_input {
+            Some(input) if self.invalid_code => screen.print(text_x, offset_y + 13, &format!("Invalid code: {}_", input)),
+            Some(input) => screen.print(text_x, offset_y + 13, &format!("Code: {}_", input)),
+            None => screen.print(text_x, offset_y + 13, "L: Load code"),
+        }
+        screen.set_pxl(offset_x, offset_y + self.cursor_position.to_index() as i32 * 2, pxl('*'))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    mod cursor_position {
+        use crate::main_menu::{wrapping_offset, MainMenuCursorPosition};
+
+        #[test]
+        fn next_then_prev_is_identity() {
+            MainMenuCursorPosition::ALL.iter().for_each(|position| {
+                assert_eq!(*position, position.next().prev());
+                assert_eq!(*position, position.prev().next());
+            });
+        }
+
+        #[test]
+        fn index_round_trip() {
+            MainMenuCursorPosition::ALL.iter().for_each(|position| {
+                assert_eq!(*position, MainMenuCursorPosition::from_index(position.to_index()));
+            });
+        }
+
+        #[test]
+        fn cycle_wraps() {
+            assert_eq!(MainMenuCursorPosition::Width, MainMenuCursorPosition::StartGame.next());
+            assert_eq!(MainMenuCursorPosition::StartGame, MainMenuCursorPosition::Width.prev());
+            assert_eq!(MainMenuCursorPosition::Height, MainMenuCursorPosition::MineCount.cycle(-6));
+        }
+
+        #[test]
+        fn wrapping_offset_both_directions() {
+            assert_eq!(0, wrapping_offset(3, 1, 4));
+            assert_eq!(3, wrapping_offset(0, -1, 4));
+            assert_eq!(2, wrapping_offset(2, 8, 4));
+        }
+    }
+
+    mod values {
+        use crate::geom::Size2D;
+        use crate::main_menu::{MainMenu, MainMenuCursorPosition, FAST_STEP, MAX_LIVES};
+
+        #[test]
+        fn default_is_rectangular() {
+            let menu = MainMenu::default();
+            assert_ne!(menu.width, menu.height);
+        }
+
+        #[test]
+        fn dimensions_clamped_to_one() {
+            let mut menu = MainMenu {
+                cursor_position: MainMenuCursorPosition::Width,
+                width: 1,
+                ..MainMenu::default()
+            };
+            menu.adjust(-1);
+            assert_eq!(1, menu.width);
+            menu.cursor_position = MainMenuCursorPosition::Height;
+            menu.adjust(-100);
+            assert_eq!(1, menu.height);
+        }
+
+        #[test]
+        fn mines_clamped_to_leave_a_safe_cell() {
+            let mut menu = MainMenu {
+                cursor_position: MainMenuCursorPosition::Width,
+                width: 2,
+                height: 2,
+                mine_count: 3,
+                lives: 1,
+                ..MainMenu::default()
+            };
+            menu.adjust(-1);
+            assert_eq!(1, menu.mine_count);
+        }
+
+        #[test]
+        fn lives_clamped() {
+            let mut menu = MainMenu {
+                cursor_position: MainMenuCursorPosition::Lives,
+                ..MainMenu::default()
+            };
+            menu.adjust(-1);
+            assert_eq!(1, menu.lives);
+            menu.adjust(100);
+            assert_eq!(MAX_LIVES, menu.lives);
+        }
+
+        #[test]
+        fn board_clamped_to_screen() {
+            let mut menu = MainMenu {
+                cursor_position: MainMenuCursorPosition::Width,
+                width: 30,
+                height: 16,
+                mine_count: 99,
+                customized: true,
+                ..MainMenu::default()
+            };
+            menu.set_screen(Size2D(42, 15));
+            assert_eq!((20, 13), (menu.width, menu.height));
+            menu.adjust(FAST_STEP);
+            assert_eq!(20, menu.width);
+        }
+
+        #[test]
+        fn fresh_menu_suggests_board_for_screen() {
+            let mut menu = MainMenu::default();
+            menu.set_screen(Size2D(42, 25));
+            assert_eq!((16, 10, 20), (menu.width, menu.height, menu.mine_count));
+            menu.set_screen(Size2D(30, 12));
+            assert_eq!((9, 9, 10), (menu.width, menu.height, menu.mine_count));
+            menu.set_screen(Size2D(13, 6));
+            assert_eq!((6, 4, 3), (menu.width, menu.height, menu.mine_count));
+        }
+
+        #[test]
+        fn customized_board_is_only_clamped() {
+            let mut menu = MainMenu {
+                cursor_position: MainMenuCursorPosition::Width,
+                ..MainMenu::default()
+            };
+            menu.adjust(-1);
+            menu.set_screen(Size2D(30, 12));
+            assert_eq!((14, 10, 20), (menu.width, menu.height, menu.mine_count));
+        }
+
+        #[test]
+        fn fast_step_clamped_at_boundaries() {
+            let mut menu = MainMenu {
+                cursor_position: MainMenuCursorPosition::Width,
+                width: 25,
+                ..MainMenu::default()
+            };
+            menu.adjust(FAST_STEP);
+            assert_eq!(35, menu.width);
+            menu.adjust(-FAST_STEP);
+            menu.adjust(-FAST_STEP);
+            menu.adjust(-FAST_STEP);
+            assert_eq!(5, menu.width);
+            menu.adjust(-FAST_STEP);
+            assert_eq!(1, menu.width);
+            menu.width = 250;
+            menu.adjust(FAST_STEP);
+            assert_eq!(u8::MAX as usize, menu.width);
+            menu.cursor_position = MainMenuCursorPosition::Lives;
+            menu.adjust(FAST_STEP);
+            assert_eq!(MAX_LIVES, menu.lives);
+        }
+    }
+
+    mod navigation {
+        use std::time::Duration;
+        use console_engine::KeyCode;
+        use crate::input::MockInput;
+        use crate::main_menu::{MainMenu, MainMenuCursorPosition};
+        use crate::state::GameState;
+
+        #[test]
+        fn arrows_move_cursor_and_adjust_values() {
+            let mut menu = MainMenu::default();
+            let width = menu.width;
+            assert!(menu.update(&MockInput::pressing(KeyCode::Down), Duration::ZERO).is_none());
+            assert_eq!(MainMenuCursorPosition::Width, menu.cursor_position);
+            menu.update(&MockInput::pressing(KeyCode::Right), Duration::ZERO);
+            assert_eq!(width + 1, menu.width);
+            menu.update(&MockInput::pressing(KeyCode::Up), Duration::ZERO);
+            menu.update(&MockInput::pressing(KeyCode::Up), Duration::ZERO);
+            assert_eq!(MainMenuCursorPosition::Lives, menu.cursor_position);
+        }
+
+        #[test]
+        fn escape_exits() {
+            let mut menu = MainMenu::default();
+            assert!(menu.update(&MockInput::pressing(KeyCode::Esc), Duration::ZERO).is_some());
+        }
+    }
+
+    mod share_code {
+        use crate::main_menu::MainMenu;
+
+        #[test]
+        fn invalid_code_stays_in_input() {
+            let mut menu = MainMenu {
+                code_input: Some(String::from("bombe:12:9x")),
+                ..MainMenu::default()
+            };
+            assert!(menu.submit_code().is_none());
+            assert!(menu.invalid_code);
+            assert_eq!(Some("bombe:12:9x"), menu.code_input.as_deref());
+        }
+
+        #[test]
+        fn unplayable_code_is_invalid() {
+            ["bombe:1:0x0:5", "bombe:1:3x3:10"].iter().for_each(|code| {
+                let mut menu = MainMenu {
+                    code_input: Some(String::from(*code)),
+                    ..MainMenu::default()
+                };
+                assert!(menu.submit_code().is_none());
+                assert!(menu.invalid_code);
+            });
+        }
+
+        #[test]
+        fn valid_code_starts_game() {
+            let mut menu = MainMenu {
+                code_input: Some(String::from("bombe:12:9x9:10")),
+                ..MainMenu::default()
+            };
+            assert!(menu.submit_code().is_some());
+            assert!(!menu.invalid_code);
+        }
+    }
+
+    mod header_variant {
+        use std::time::Duration;
+        use console_engine::screen::Screen;
+        use crate::main_menu::{rainbow_color, HeaderVariant, MainMenu, RAINBOW_COLORS, RAINBOW_STEP};
+        use crate::state::GameState;
+
+        #[test]
+        fn art_on_large_screen() {
+            assert_eq!(HeaderVariant::Art, HeaderVariant::for_screen(80, 40));
+        }
+
+        #[test]
+        fn art_on_screen_as_wide_as_header() {
+            assert_eq!(HeaderVariant::Art, HeaderVariant::for_screen(48, 40));
+        }
+
+        #[test]
+        fn plain_on_narrow_screen() {
+            assert_eq!(HeaderVariant::Plain, HeaderVariant::for_screen(42, 40));
+        }
+
+        #[test]
+        fn plain_on_short_screen() {
+            assert_eq!(HeaderVariant::Plain, HeaderVariant::for_screen(80, 15));
+        }
+
+        #[test]
+        fn art_above_all_menu_rows() {
+            assert_eq!(HeaderVariant::Art, HeaderVariant::for_screen(80, 30));
+            assert_eq!(HeaderVariant::Plain, HeaderVariant::for_screen(80, 29));
+        }
+
+        #[test]
+        fn menu_rows_fit_on_short_screen() {
+            let mut screen = Screen::new(42, 15);
+            MainMenu::default().draw(&mut screen);
+            let text = |y: i32| (0..42).map(|x| screen.get_pxl(x, y).unwrap().chr).collect::<String>();
+            assert!(text(0).contains("Width"));
+            assert!(text(13).contains("L: Load code"));
+        }
+
+        #[test]
+        fn plain_on_tiny_screen() {
+            assert_eq!(HeaderVariant::Plain, HeaderVariant::for_screen(10, 5));
+        }
+
+        #[test]
+        fn rainbow_shifts_over_time() {
+            assert_eq!(RAINBOW_COLORS[2], rainbow_color(2, Duration::ZERO));
+            assert_eq!(RAINBOW_COLORS[2], rainbow_color(2, RAINBOW_STEP - Duration::from_millis(1)));
+            assert_eq!(RAINBOW_COLORS[3], rainbow_color(2, RAINBOW_STEP));
+            assert_eq!(RAINBOW_COLORS[0], rainbow_color(5, RAINBOW_STEP));
+            assert_eq!(RAINBOW_COLORS[2], rainbow_color(2, RAINBOW_STEP * RAINBOW_COLORS.len() as u32));
+        }
     }
 }
@@ -0,0 +1,58 @@
+use std::fmt;
+use console_engine::Color;
+use console_engine::pixel::pxl_fbg;
+use console_engine::screen::Screen;
+
+/// Something a board can be drawn onto, one character cell at a time.
+pub trait Renderer {
+    fn draw_cell(&mut self, col: usize, row: usize, glyph: char, fg: Color, bg: Color);
+}
+
+/// Draws onto a `console_engine` screen.
+pub struct ScreenRenderer<'a> {
+    screen: &'a mut Screen,
+}
+
+impl<'a> ScreenRenderer<'a> {
+    pub fn new(screen: &'a mut Screen) -> Self {
+        Self {
+            screen,
+        }
+    }
+}
+
+impl Renderer for ScreenRenderer<'_> {
+    fn draw_cell(&mut self, col: usize, row: usize, glyph: char, fg: Color, bg: Color) {
+        self.screen.set_pxl(col as i32, row as i32, pxl_fbg(glyph, fg, bg));
+    }
+}
+
+/// Collects the glyphs as lines of text, dropping colors. Cells outside the size given to `new` are ignored.
+pub struct StringRenderer {
+    rows: Vec<Vec<char>>,
+}
+
+impl StringRenderer {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            rows: vec![vec![' '; width]; height],
+        }
+    }
+}
+
+impl Renderer for StringRenderer {
+    fn draw_cell(&mut self, col: usize, row: usize, glyph: char, _fg: Color, _bg: Color) {
+        if let Some(cell) = self.rows.get_mut(row).and_then(|row| row.get_mut(col)) {
+            *cell = glyph;
+        }
+    }
+}
+
+impl fmt::Display for StringRenderer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let lines = self.rows.iter()
+            .map(|row| row.iter().collect::<String>())
+            .collect::<Vec<_>>();
+        write!(f, "{}", lines.join("\n"))
+    }
+}
@@ -0,0 +1,80 @@
+use std::time::Duration;
+use crate::geom::Point2D;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum MoveKind {
+    Open,
+    Flag,
+    AutoFlag,
+    Chord,
+    Hint,
+    Undo,
+    /// A mine given away after playing too slowly, see `GameSettings::penalty_interval`.
+    Penalty,
+}
+
+impl MoveKind {
+    fn name(&self) -> &'static str {
+        match self {
+            MoveKind::Open => "open",
+            MoveKind::Flag => "flag",
+            MoveKind::AutoFlag => "autoflag",
+            MoveKind::Chord => "chord",
+            MoveKind::Hint => "hint",
+            MoveKind::Undo => "undo",
+            MoveKind::Penalty => "penalty",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Move {
+    pub time: Duration,
+    pub kind: MoveKind,
+    pub location: Point2D,
+}
+
+/// Every move that changed the field, in the order they were made.
+#[derive(Debug, Clone, Default)]
+pub struct MoveLog {
+    moves: Vec<Move>,
+}
+
+impl MoveLog {
+    pub fn push(&mut self, time: Duration, kind: MoveKind, location: Point2D) {
+        self.moves.push(Move {
+            time,
+            kind,
+            location,
+        });
+    }
+
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+
+    /// The `header` line describing the board, see `Game::replay_log`, followed by one
+    /// move per line as `<millis> <kind> <x> <y>`.
+    pub fn serialize(&self, header: &str) -> String {
+        std::iter::once(header.to_string())
+            .chain(self.moves.iter()
+                .map(|m| format!("{} {} {} {}", m.time.as_millis(), m.kind.name(), m.location.0, m.location.1)))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use crate::geom::Point2D;
+    use crate::replay::{MoveKind, MoveLog};
+
+    #[test]
+    fn serialize_in_order() {
+        let mut log = MoveLog::default();
+        log.push(Duration::from_millis(1500), MoveKind::Open, Point2D(1, 2));
+        log.push(Duration::from_millis(2750), MoveKind::Flag, Point2D(3, 0));
+        assert_eq!("bombe:7:9x9:10\n1500 open 1 2\n2750 flag 3 0", log.serialize("bombe:7:9x9:10"));
+    }
+}
@@ -0,0 +1,88 @@
+use std::fmt;
+use crate::game::GameSettings;
+use crate::geom::Size2D;
+
+const PREFIX: &str = "bombe";
+
+/// Seed and dimensions of a generated board, written as `bombe:SEED:WxH:M` so others can play it too.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShareCode {
+    pub seed: u64,
+    pub size: Size2D,
+    pub mine_count: usize,
+}
+
+impl ShareCode {
+    pub fn parse(code: &str) -> Option<ShareCode> {
+        let mut parts = code.trim().split(':');
+        if parts.next()? != PREFIX {
+            return None;
+        }
+        let seed = parts.next()?.parse().ok()?;
+        let (width, height) = parts.next()?.split_once('x')?;
+        let mine_count = parts.next()?.parse().ok()?;
+        if parts.next().is_some() {
+            return None;
+        }
+        Some(ShareCode {
+            seed,
+            size: Size2D(width.parse().ok()?, height.parse().ok()?),
+            mine_count,
+        })
+    }
+
+    /// Settings that regenerate the shared board when used with `Game::seeded` and the seed.
+    pub fn settings(&self) -> GameSettings {
+        GameSettings {
+            size: self.size.clone(),
+            mine_count: self.mine_count,
+            ..GameSettings::default()
+        }
+    }
+}
+
+/// Puts `text` on the system clipboard. Fails when built without the `clipboard`
+/// feature or when no clipboard is available, e.g. over SSH.
+#[cfg(feature = "clipboard")]
+pub fn copy_to_clipboard(text: &str) -> bool {
+    arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.set_text(text))
+        .is_ok()
+}
+
+#[cfg(not(feature = "clipboard"))]
+pub fn copy_to_clipboard(_text: &str) -> bool {
+    false
+}
+
+impl fmt::Display for ShareCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}x{}:{}", PREFIX, self.seed, self.size.0, self.size.1, self.mine_count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::geom::Size2D;
+    use crate::share::ShareCode;
+
+    #[test]
+    fn format_parse_round_trip() {
+        let code = ShareCode {
+            seed: 1234567890,
+            size: Size2D(30, 16),
+            mine_count: 99,
+        };
+        assert_eq!("bombe:1234567890:30x16:99", code.to_string());
+        assert_eq!(Some(code.clone()), ShareCode::parse(&code.to_string()));
+    }
+
+    #[test]
+    fn parse_rejects_malformed_codes() {
+        assert_eq!(None, ShareCode::parse("bomb:1:9x9:10"));
+        assert_eq!(None, ShareCode::parse("bombe:x:9x9:10"));
+        assert_eq!(None, ShareCode::parse("bombe:1:9:10"));
+        assert_eq!(None, ShareCode::parse("bombe:1:9x9:10:extra"));
+        assert_eq!(None, ShareCode::parse("bombe:1:9x9"));
+    }
+}
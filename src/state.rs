@@ -1,5 +1,6 @@
-use console_engine::ConsoleEngine;
+use std::time::Duration;
 use console_engine::screen::Screen;
+use crate::input::Input;
 
 pub enum SystemEvent {
     ChangeState(Box<dyn GameState>),
@@ -7,7 +8,14 @@ pub enum SystemEvent {
 }
 
 pub trait GameState {
-    fn update(&mut self, engine: &ConsoleEngine) -> Option<SystemEvent>;
+    /// Handles input for one frame. `dt` is the time passed since the previous frame.
+    fn update(&mut self, input: &dyn Input, dt: Duration) -> Option<SystemEvent>;
+
+    /// Whether the last update changed what `draw` would show. States that cannot tell
+    /// are drawn every frame.
+    fn needs_redraw(&self) -> bool {
+        true
+    }
 
     fn draw(&self, screen: &mut Screen);
 }
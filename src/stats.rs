@@ -0,0 +1,202 @@
+use std::time::Duration;
+use console_engine::KeyCode;
+use console_engine::screen::Screen;
+use crate::events::{EventSink, NoEvents};
+use crate::game::{Game, GameSettings, GameStats};
+use crate::geom::Size2D;
+use crate::input::Input;
+use crate::main_menu::MainMenu;
+use crate::state::{GameState, SystemEvent};
+
+const KEY_REPLAY: KeyCode = KeyCode::Char('r');
+const KEY_MENU: KeyCode = KeyCode::Char('m');
+/// Scores kept in the high-score table.
+const HIGH_SCORE_COUNT: usize = 5;
+
+/// Score rewarding many and densely placed mines, decaying with the time taken.
+/// Lost or assisted games score nothing.
+pub fn score(stats: &GameStats, size: &Size2D, mine_count: usize) -> u64 {
+    let cells = (size.0 * size.1) as u64;
+    if !stats.won || cells == 0 {
+        return 0;
+    }
+    let mines = mine_count as u64;
+    let difficulty = mines * 10_000 * mines / cells;
+    difficulty * 60 / (stats.duration.as_secs() + 60)
+}
+
+/// Best scores of the session, highest first. Only kept in memory, handed on from game to game.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HighScores {
+    scores: Vec<u64>,
+}
+
+impl HighScores {
+    /// Adds `score` if it makes the table, returning its rank counted from 0.
+    pub fn insert(&mut self, score: u64) -> Option<usize> {
+        if score == 0 {
+            return None;
+        }
+        let rank = self.scores.iter().position(|best| score > *best).unwrap_or(self.scores.len());
+        if rank >= HIGH_SCORE_COUNT {
+            return None;
+        }
+        self.scores.insert(rank, score);
+        self.scores.truncate(HIGH_SCORE_COUNT);
+        Some(rank)
+    }
+
+    pub fn scores(&self) -> &[u64] {
+        &self.scores
+    }
+}
+
+pub struct StatsScreen {
+    stats: GameStats,
+    settings: GameSettings,
+    /// Passed on to the next game.
+    events: Box<dyn EventSink>,
+    high_scores: HighScores,
+    /// Place of this game's score in `high_scores`, if it made the table.
+    rank: Option<usize>,
+}
+
+impl StatsScreen {
+    /// Stats of a finished game, entering its score into `high_scores` unless it was a practice game.
+    pub fn new(stats: GameStats, settings: GameSettings, mut high_scores: HighScores) -> Self {
+        let rank = if settings.practice { None } else { high_scores.insert(score(&stats, &settings.size, settings.mine_count)) };
+        Self {
+            stats,
+            settings,
+            events: Box::new(NoEvents),
+            high_scores,
+            rank,
+        }
+    }
+
+    pub fn set_event_sink(&mut self, events: Box<dyn EventSink>) {
+        self.events = events;
+    }
+
+    fn lines(&self) -> Vec<String> {
+        let mut lines = vec![
+            String::from(if self.stats.won { "Victory!" } else { "Defeat" }),
+            String::new(),
+            format!("Time: {}s", self.stats.duration.as_secs()),
+            format!("Cells opened: {}", self.stats.cells_opened),
+            format!("Flags used: {}", self.stats.flags_used),
+            format!("Actions: {}", self.stats.actions),
+            if self.settings.practice {
+                String::from("Score: practice")
+            } else {
+                format!("Score: {}", score(&self.stats, &self.settings.size, self.settings.mine_count))
+            },
+        ];
+        if !self.high_scores.scores().is_empty() {
+            lines.push(String::new());
+            lines.push(String::from("High scores:"));
+            lines.extend(self.high_scores.scores().iter().enumerate().map(|(rank, score)| {
+                let marker = if self.rank == Some(rank) { " <" } else { "" };
+                format!("{}. {}{}", rank + 1, score, marker)
+            }));
+        }
+        lines.push(String::new());
+        lines.push(String::from("R: Replay  M: Menu  Esc: Quit"));
+        lines
+    }
+}
+
+impl GameState for StatsScreen {
+    fn update(&mut self, input: &dyn Input, _dt: Duration) -> Option<SystemEvent> {
+        if input.is_key_pressed(KeyCode::Esc) {
+            return Some(SystemEvent::Exit);
+        }
+        if input.is_key_pressed(KEY_REPLAY) {
+            let mut game = Game::random(self.settings.clone()).ok()?;
+            game.set_event_sink(std::mem::replace(&mut self.events, Box::new(NoEvents)));
+            game.set_high_scores(std::mem::take(&mut self.high_scores));
+            return Some(SystemEvent::ChangeState(Box::new(game)));
+        }
+        if input.is_key_pressed(KEY_MENU) {
+            return Some(SystemEvent::ChangeState(Box::<MainMenu>::default()));
+        }
+        None
+    }
+
+    fn draw(&self, screen: &mut Screen) {
+        let lines = self.lines();
+        let offset_y = screen.get_height() as i32 / 2 - lines.len() as i32 / 2;
+        lines.iter().enumerate().for_each(|(idx, line)| {
+            let offset_x = screen.get_width() as i32 / 2 - line.len() as i32 / 2;
+            screen.print(offset_x, offset_y + idx as i32, line);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use crate::game::{GameSettings, GameStats};
+    use crate::geom::Size2D;
+    use crate::stats::{score, HighScores, StatsScreen, HIGH_SCORE_COUNT};
+
+    fn stats(secs: u64, won: bool) -> GameStats {
+        GameStats {
+            duration: Duration::from_secs(secs),
+            cells_opened: 0,
+            flags_used: 0,
+            actions: 0,
+            won,
+        }
+    }
+
+    #[test]
+    fn exact_score() {
+        assert_eq!(5000, score(&stats(60, true), &Size2D(10, 10), 10));
+    }
+
+    #[test]
+    fn denser_and_faster_scores_higher() {
+        let easy = score(&stats(60, true), &Size2D(10, 10), 10);
+        let hard = score(&stats(30, true), &Size2D(10, 10), 40);
+        assert_eq!(106666, hard);
+        assert!(hard > easy);
+    }
+
+    #[test]
+    fn loss_scores_nothing() {
+        assert_eq!(0, score(&stats(10, false), &Size2D(10, 10), 10));
+    }
+
+    #[test]
+    fn high_scores_keep_best_first() {
+        let mut high_scores = HighScores::default();
+        assert_eq!(Some(0), high_scores.insert(300));
+        assert_eq!(Some(1), high_scores.insert(100));
+        assert_eq!(Some(1), high_scores.insert(200));
+        assert_eq!(None, high_scores.insert(0));
+        (0..HIGH_SCORE_COUNT).for_each(|_| {
+            high_scores.insert(400);
+        });
+        assert_eq!(None, high_scores.insert(300));
+        assert_eq!(&[400; HIGH_SCORE_COUNT], high_scores.scores());
+    }
+
+    #[test]
+    fn stats_screen_enters_score() {
+        let settings = GameSettings {
+            size: Size2D(10, 10),
+            mine_count: 10,
+            ..GameSettings::default()
+        };
+        let screen = StatsScreen::new(stats(60, true), settings.clone(), HighScores::default());
+        assert_eq!(Some(0), screen.rank);
+        assert!(screen.lines().contains(&String::from("1. 5000 <")));
+        let practice = GameSettings {
+            practice: true,
+            ..settings
+        };
+        let screen = StatsScreen::new(stats(60, true), practice, HighScores::default());
+        assert!(screen.high_scores.scores().is_empty());
+    }
+}
@@ -0,0 +1,45 @@
+use bombers::game::{generate_from_positions, CellState, CellType, CellView, Minefield, RandomMineFieldGenerator};
+use bombers::geom::{Point2D, Size2D};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+#[test]
+fn read_cells_through_public_api() {
+    let minefield = generate_from_positions(Size2D(3, 2), &[Point2D(1, 0)]).unwrap();
+    assert_eq!(&Size2D(3, 2), minefield.size());
+    assert_eq!(Some(CellView { cell_type: CellType::Mine, state: CellState::Closed }), minefield.cell_at(&Point2D(1, 0)));
+    assert_eq!(Some(CellView { cell_type: CellType::Water, state: CellState::Closed }), minefield.cell_at(&Point2D(2, 1)));
+    assert_eq!(None, minefield.cell_at(&Point2D(3, 0)));
+}
+
+#[test]
+fn iterate_all_cells() {
+    let minefield = generate_from_positions(Size2D(3, 2), &[Point2D(0, 1), Point2D(2, 0)]).unwrap();
+    let cells = minefield.cells().collect::<Vec<_>>();
+    assert_eq!(6, cells.len());
+    let mines = cells.iter()
+        .filter(|(_, cell)| cell.cell_type == CellType::Mine)
+        .map(|(location, _)| location.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(vec![Point2D(0, 1), Point2D(2, 0)], mines);
+}
+
+#[test]
+fn count_opened_cells() {
+    let mut minefield = Minefield::from_pattern("*..\n..*").unwrap();
+    let opened = |minefield: &Minefield| minefield.cells()
+        .filter(|(_, cell)| cell.state == CellState::Opened)
+        .count();
+    assert_eq!(0, opened(&minefield));
+    minefield.open_all_safe();
+    assert_eq!(4, opened(&minefield));
+}
+
+#[test]
+fn read_size_and_mine_count_of_generated_board() {
+    let mut generator = RandomMineFieldGenerator { random: StdRng::seed_from_u64(7) };
+    let minefield = generator.generate(Size2D(9, 6), 12).unwrap();
+    assert_eq!(&Size2D(9, 6), minefield.size());
+    assert_eq!(12, minefield.mine_count());
+    assert_eq!(Ok(()), minefield.validate());
+}